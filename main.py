@@ -6,21 +6,34 @@ Run locally with:
   uv add PySide6 httpx redis pyqtgraph
   uv run python main.py
 
-Adjust API_BASE and REDIS settings below.
+Settings are read from a JSON file in the user config dir (see
+`default_settings_path`); any field can be overridden on the command line,
+e.g. `uv run python main.py --api-base http://192.168.0.101:8000`.
 """
 
 from __future__ import annotations
 
+import argparse
+import base64
+import getpass
+import hashlib
+import hmac
+import itertools
 import json
+import operator
+import os
+import queue
 import serial
 import socket
 import sys
 import threading
 import re
 import time
+import uuid
 from math import isfinite
-from dataclasses import dataclass
-from typing import Any, Optional
+from dataclasses import asdict, dataclass, field, fields
+from enum import IntEnum
+from typing import Any, Callable, Iterable, NamedTuple, Optional
 from pathlib import Path
 from datetime import datetime
 import csv
@@ -29,6 +42,7 @@ import httpx
 import pyqtgraph as pg
 from PySide6.QtCore import (
     QObject,
+    QRectF,
     QThread,
     QThreadPool,
     QRunnable,
@@ -37,18 +51,38 @@ from PySide6.QtCore import (
     Qt,
     QTimer,
 )
-from PySide6.QtGui import QAction
+from PySide6.QtGui import (
+    QAction,
+    QActionGroup,
+    QColor,
+    QFontDatabase,
+    QKeySequence,
+    QPainter,
+    QPen,
+    QShortcut,
+)
+from PySide6.QtSvg import QSvgRenderer
+from PySide6.QtSvgWidgets import QGraphicsSvgItem
 from PySide6.QtWidgets import (
     QApplication,
+    QCheckBox,
+    QDoubleSpinBox,
+    QGraphicsRectItem,
+    QGraphicsScene,
+    QGraphicsSimpleTextItem,
+    QGraphicsView,
     QGridLayout,
     QGroupBox,
     QHBoxLayout,
     QVBoxLayout,
     QScrollArea,
+    QSlider,
+    QSpinBox,
     QSplitter,
     QLabel,
     QLineEdit,
     QMainWindow,
+    QMenu,
     QMessageBox,
     QPushButton,
     QTextEdit,
@@ -56,6 +90,11 @@ from PySide6.QtWidgets import (
     QDialog,
     QFormLayout,
     QDialogButtonBox,
+    QInputDialog,
+    QFileDialog,
+    QListWidget,
+    QListWidgetItem,
+    QTabWidget,
 )
 
 pg.setConfigOptions(useOpenGL=False, antialias=True)
@@ -66,26 +105,612 @@ pg.setConfigOptions(useOpenGL=False, antialias=True)
 # -----------------------------
 
 
+APP_NAME = "prop-control-gui"
+
+
+def check_sequence_steps(steps: Any) -> list[dict[str, Any]]:
+    """Validate abort sequence steps: {"control", "action"} or {"delay_s"}."""
+    if not isinstance(steps, list):
+        raise ValueError("abort_sequence must be a list")
+    for i, step in enumerate(steps, 1):
+        if not isinstance(step, dict):
+            raise ValueError(f"abort step {i} must be an object")
+        if "delay_s" in step:
+            try:
+                delay = float(step["delay_s"])
+            except (TypeError, ValueError):
+                delay = -1.0
+            if set(step) != {"delay_s"} or delay < 0:
+                raise ValueError(f"abort step {i}: delay needs only delay_s >= 0")
+        elif set(step) != {"control", "action"}:
+            raise ValueError(f"abort step {i} needs control and action")
+        elif str(step["action"]).upper() not in {"OPEN", "CLOSE"}:
+            raise ValueError(f"abort step {i}: action must be OPEN or CLOSE")
+    return steps
+
+
+def user_config_dir() -> Path:
+    """Per-user config directory for this app (platform dependent)."""
+    if sys.platform == "win32":
+        base = os.environ.get("APPDATA") or Path.home() / "AppData" / "Roaming"
+    elif sys.platform == "darwin":
+        base = Path.home() / "Library" / "Application Support"
+    else:
+        base = os.environ.get("XDG_CONFIG_HOME") or Path.home() / ".config"
+    return Path(base) / APP_NAME
+
+
+def default_settings_path() -> Path:
+    return user_config_dir() / "settings.json"
+
+
+# Settings a station profile may override; everything else is global
+PROFILE_KEYS = (
+    "api_base",
+    "commands_path",
+    "redis_host",
+    "redis_port",
+    "redis_channel",
+    "redis_mode",
+    "redis_stream",
+    "key_switch_port",
+    "key_switch_required",
+    "lease_required",
+    "log_dir",
+)
+
+
+def default_profiles() -> dict[str, dict[str, Any]]:
+    return {
+        "bench": {
+            "api_base": "http://localhost:8000",
+            "redis_host": "localhost",
+        },
+        "pad": {
+            "api_base": "http://192.168.0.101:8000",
+            "redis_host": "192.168.0.101",
+        },
+        "simulator": {
+            "api_base": "http://localhost:8000",
+            "redis_host": "localhost",
+            "key_switch_required": False,
+            "lease_required": False,
+            "log_dir": "data/simulator",
+        },
+    }
+
+
+# Per-profile credentials; held in a CredentialStore, never in the settings file
+CREDENTIAL_KEYS = ("api_username", "api_password", "redis_username", "redis_password")
+
+
 @dataclass
 class Config:
     api_base: str = "http://localhost:8000"
     commands_path: str = "/v1/command"
-    api_username: str = "noah"
-    api_password: str = "stinkylion"
     redis_host: str = "localhost"
     redis_port: int = 6379
     redis_channel: str = "log"
-    redis_username: str = "server"
-    redis_password: str = "propteambestteam"
+    # pubsub: tail redis_channel; stream: XREAD redis_stream and replay on reconnect
+    redis_mode: str = "pubsub"
+    redis_stream: str = "log"
+    key_switch_port: str = ""  # empty -> auto-detect by serial number / USB ID
+    key_switch_serial: str = ""  # USB serial number of the key switch adapter
+    # VID:PID of adapters to accept when auto-detecting (CP210x, CH340, ESP32-S3)
+    key_switch_usb_ids: list[str] = field(
+        default_factory=lambda: ["10C4:EA60", "1A86:7523", "303A:1001"]
+    )
+    # No valid key switch reading for this long -> treat as disarmed (0 = never)
+    key_switch_timeout_s: float = 3.0
+    # False only for setups without a key switch (e.g. the simulator)
+    key_switch_required: bool = True
+    log_dir: str = ""  # empty -> data/ next to main.py; relative -> from main.py
+    profile: str = "bench"
+    profiles: dict[str, dict[str, Any]] = field(default_factory=default_profiles)
+    # keyring | file | auto (keyring if a backend exists, else encrypted file)
+    credential_backend: str = "auto"
+    # Seconds to wait for a CONTROL/STATUS confirmation before flagging a valve
+    command_ack_timeout_s: float = 2.0
+    # A control's indicator is marked stale when no report arrives for this long
+    control_stale_s: float = 5.0
+    # Control authority lease (Redis key with TTL) shared by every GUI instance;
+    # False -> no lease needed (single-station setups, the simulator)
+    lease_required: bool = True
+    lease_key: str = "prop:control_lease"
+    lease_ttl_s: float = 6.0
+    # Ordered safing steps, e.g. [{"control": "AVMAINOX", "action": "CLOSE"},
+    # {"delay_s": 0.5}, {"control": "AVVENT", "action": "OPEN"}].
+    # Empty -> close every Close All control.
+    abort_sequence: list[dict[str, Any]] = field(default_factory=list)
+    abort_hotkey: str = "F12"
+    # Sensor limits checked on every sample, e.g. [{"name": "Chamber over-pressure",
+    # "sensor": "PTRun", "op": ">", "value": 800, "duration_ms": 50,
+    # "action": "abort"}]  (action: abort -> safing + alarm, alarm -> alarm only)
+    redlines: list[dict[str, Any]] = field(default_factory=list)
+    # Commands refused while a condition holds, e.g. [{"control": "AVOX",
+    # "action": "OPEN", "sensor": "PTPreInjector", "op": ">", "value": 300}]
+    # (action OPEN, CLOSE, or SET for analog setpoints)
+    interlocks: list[dict[str, Any]] = field(default_factory=list)
+    # A sensor reading older than this counts as missing: interlocks then block,
+    # waits keep waiting and go/no-go conditions fail (0 = never stale)
+    sensor_stale_s: float = 2.0
+    # Ignition countdown starts at T-countdown_s; from T-terminal_count_s on all
+    # go/no-go checks must pass or the count holds. At T-0 firing_sequence
+    # (sequencer steps) is started.
+    countdown_s: float = 60.0
+    terminal_count_s: float = 10.0
+    go_polls: list[str] = field(
+        default_factory=lambda: ["Area clear", "Test conductor GO"]
+    )
+    # Sensor conditions that must hold for GO, e.g. [{"sensor": "PTN2",
+    # "op": ">", "value": 400}]
+    go_conditions: list[dict[str, Any]] = field(default_factory=list)
+    firing_sequence: list[dict[str, Any]] = field(default_factory=list)
+    # P&ID diagram (SVG; relative -> from main.py) and element IDs to overlay:
+    # control name -> valve element, sensor ("NAME" or "DEVICE:NAME") -> element
+    pid_svg: str = ""
+    pid_valves: dict[str, str] = field(default_factory=dict)
+    pid_sensors: dict[str, str] = field(default_factory=dict)
+    # Runtime-only: filled from the CredentialStore for the active profile
+    api_username: str = ""
+    api_password: str = ""
+    redis_username: str = ""
+    redis_password: str = ""
+
+    def credentials(self) -> dict[str, str]:
+        return {k: getattr(self, k) for k in CREDENTIAL_KEYS}
+
+    def update(self, values: dict[str, Any]) -> None:
+        """Apply a mapping of field name -> value, coercing to each field's type.
+        Raises ValueError on unknown keys or values that cannot be converted."""
+        known = {f.name for f in fields(self)}
+        unknown = sorted(set(values) - known)
+        if unknown:
+            raise ValueError(f"unknown setting(s): {', '.join(unknown)}")
+        for name, value in values.items():
+            current = getattr(self, name)
+            try:
+                if isinstance(current, bool):
+                    if isinstance(value, str):
+                        value = value.strip().lower() in {"1", "true", "yes", "on"}
+                    value = bool(value)
+                elif isinstance(current, int):
+                    value = int(value)
+                elif isinstance(current, float):
+                    value = float(value)
+                elif name == "abort_sequence":
+                    value = check_sequence_steps(value)
+                elif name == "redlines":
+                    value = check_redlines(value)
+                elif name == "interlocks":
+                    value = check_interlocks(value)
+                elif name == "go_conditions":
+                    value = check_go_conditions(value)
+                elif name == "firing_sequence":
+                    value = check_procedure_steps(value)
+                elif isinstance(current, list):
+                    if not isinstance(value, list):
+                        raise ValueError(f"{name} must be a list")
+                    value = [str(v) for v in value]
+                elif name == "profiles":
+                    value = self._check_profiles(value)
+                elif name in ("pid_valves", "pid_sensors"):
+                    if not isinstance(value, dict):
+                        raise ValueError(f"{name} must be an object")
+                    value = {str(k): str(v) for k, v in value.items()}
+                else:
+                    value = str(value)
+            except (KeyError, TypeError) as e:
+                # e.g. null where a number is expected
+                raise ValueError(f"{name}: invalid value ({e})") from None
+            setattr(self, name, value)
+
+    @staticmethod
+    def _check_profiles(value: Any) -> dict[str, dict[str, Any]]:
+        if not isinstance(value, dict) or not value:
+            raise ValueError("profiles must be a non-empty object")
+        for pname, overrides in value.items():
+            if not isinstance(overrides, dict):
+                raise ValueError(f"profile '{pname}' must be an object")
+            unknown = sorted(set(overrides) - set(PROFILE_KEYS))
+            if unknown:
+                raise ValueError(
+                    f"profile '{pname}' has unknown key(s): {', '.join(unknown)}"
+                )
+            try:
+                Config().update(overrides)
+            except ValueError as e:
+                raise ValueError(f"profile '{pname}': {e}") from None
+        return value
+
+    def apply_profile(self, name: str) -> None:
+        """Switch the profile-scoped settings to the named profile.
+        Keys a profile does not set fall back to the top-level settings."""
+        if name not in self.profiles:
+            raise ValueError(f"unknown profile: {name}")
+        base = getattr(self, "_base", None)
+        if base is None:
+            base = {k: getattr(self, k) for k in PROFILE_KEYS}
+            self._base = base
+        values = dict(base)
+        values.update(self.profiles[name])
+        # Validate on a scratch copy so a bad value cannot leave a half-switched
+        # config behind
+        Config().update(values)
+        self.update(values)
+        self.profile = name
+
+    @classmethod
+    def load(cls, path: Path) -> Config:
+        """Load settings from a JSON file; missing keys keep their defaults."""
+        cfg = cls()
+        data = json.loads(path.read_text(encoding="utf-8"))
+        if not isinstance(data, dict):
+            raise ValueError("top level must be a JSON object")
+        cfg.update(data)
+        cfg.mark_explicit(data)
+        return cfg
+
+    def mark_explicit(self, names: Iterable[str]) -> None:
+        """Record settings the user chose, so save() keeps them even when they
+        happen to equal the current default."""
+        self._explicit = getattr(self, "_explicit", set()) | set(names)
+
+    def save(self, path: Path) -> None:
+        """Write the explicitly set and non-default settings. Defaults are left
+        out so later changes to them reach existing installs."""
+        values = asdict(self)
+        # Persist the top-level values, not whichever profile is applied
+        values.update(getattr(self, "_base", None) or {})
+        defaults = asdict(type(self)())
+        explicit = getattr(self, "_explicit", set())
+        data = {
+            k: v for k, v in values.items() if k in explicit or v != defaults[k]
+        }
+        for key in CREDENTIAL_KEYS:
+            data.pop(key, None)
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(json.dumps(data, indent=2) + "\n", encoding="utf-8")
+
+
+# -----------------------------
+# Credential storage
+# -----------------------------
+class CredentialStoreLocked(Exception):
+    pass
+
+
+class CredentialStore:
+    """Per-profile credentials in the OS keyring, or in a passphrase-encrypted
+    file when no keyring backend is available (e.g. headless Linux).
+
+    Each profile maps to a dict with the CREDENTIAL_KEYS."""
+
+    SERVICE = APP_NAME
+    PASSPHRASE_ENV = "PROP_GUI_CREDENTIALS_PASSPHRASE"
+    AUDIT_KEY_ENTRY = "__audit_key__"  # not a profile name
+
+    def __init__(self, path: Path, backend: str = "auto") -> None:
+        self.path = path
+        self._keyring = None
+        self._fernet = None
+        self._file_data: dict[str, dict[str, str]] = {}
+        if backend not in {"auto", "keyring", "file"}:
+            raise ValueError(f"unknown credential backend: {backend}")
+        if backend != "file":
+            self._keyring = self._usable_keyring()
+            if self._keyring is None and backend == "keyring":
+                raise RuntimeError("no usable OS keyring backend")
+
+    @staticmethod
+    def _usable_keyring():
+        try:
+            import keyring
+            from keyring.backends import fail
+
+            kr = keyring.get_keyring()
+            if isinstance(kr, fail.Keyring):
+                return None
+            return kr
+        except Exception:
+            return None
+
+    @property
+    def backend_name(self) -> str:
+        if self._keyring is not None:
+            return f"OS keyring ({type(self._keyring).__name__})"
+        return f"encrypted file {self.path}"
+
+    @property
+    def needs_unlock(self) -> bool:
+        return self._keyring is None and self._fernet is None
+
+    @property
+    def is_new(self) -> bool:
+        """True if the file backend has nothing stored yet (first passphrase)."""
+        return self._keyring is None and not self.path.exists()
+
+    def _fernet_for(self, passphrase: str, salt: bytes):
+        from cryptography.fernet import Fernet
+        from cryptography.hazmat.primitives import hashes
+        from cryptography.hazmat.primitives.kdf.pbkdf2 import PBKDF2HMAC
+
+        kdf = PBKDF2HMAC(
+            algorithm=hashes.SHA256(), length=32, salt=salt, iterations=480_000
+        )
+        return Fernet(base64.urlsafe_b64encode(kdf.derive(passphrase.encode())))
+
+    def unlock(self, passphrase: str) -> None:
+        """Open (or create) the encrypted file; ValueError on a bad passphrase."""
+        from cryptography.fernet import InvalidToken
 
+        if self._keyring is not None:
+            return
+        if self.path.exists():
+            blob = json.loads(self.path.read_text(encoding="utf-8"))
+            salt = base64.b64decode(blob["salt"])
+            fernet = self._fernet_for(passphrase, salt)
+            try:
+                data = json.loads(fernet.decrypt(blob["data"].encode()))
+            except InvalidToken:
+                raise ValueError("wrong passphrase") from None
+            self._file_data = data
+        else:
+            salt = os.urandom(16)
+            fernet = self._fernet_for(passphrase, salt)
+            self._file_data = {}
+        self._salt = salt
+        self._fernet = fernet
+
+    def load(self, profile: str) -> dict[str, str]:
+        if self._keyring is not None:
+            raw = self._keyring.get_password(self.SERVICE, profile)
+            stored = json.loads(raw) if raw else {}
+        elif self._fernet is not None:
+            stored = self._file_data.get(profile, {})
+        else:
+            raise CredentialStoreLocked(self.backend_name)
+        return {k: str(stored.get(k, "")) for k in CREDENTIAL_KEYS}
+
+    def save(self, profile: str, creds: dict[str, str]) -> None:
+        values = {k: creds.get(k, "") for k in CREDENTIAL_KEYS}
+        if self._keyring is not None:
+            self._keyring.set_password(self.SERVICE, profile, json.dumps(values))
+            return
+        if self._fernet is None:
+            raise CredentialStoreLocked(self.backend_name)
+        self._file_data[profile] = values
+        self._write_file()
+
+    def _write_file(self) -> None:
+        token = self._fernet.encrypt(json.dumps(self._file_data).encode()).decode()
+        blob = {"salt": base64.b64encode(self._salt).decode(), "data": token}
+        self.path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = self.path.with_suffix(".tmp")
+        tmp.write_text(json.dumps(blob), encoding="utf-8")
+        if sys.platform != "win32":
+            tmp.chmod(0o600)
+        tmp.replace(self.path)
+
+    def audit_key(self, create: bool = True) -> Optional[bytes]:
+        """Secret key for the audit trail HMAC, kept with the credentials so a
+        trail cannot be rewritten from the file alone. Created on first use;
+        None if missing and create is False."""
+        if self._keyring is not None:
+            raw = self._keyring.get_password(self.SERVICE, self.AUDIT_KEY_ENTRY)
+        elif self._fernet is not None:
+            raw = self._file_data.get(self.AUDIT_KEY_ENTRY, {}).get("key")
+        else:
+            raise CredentialStoreLocked(self.backend_name)
+        if raw:
+            return bytes.fromhex(raw)
+        if not create:
+            return None
+        raw = os.urandom(32).hex()
+        if self._keyring is not None:
+            self._keyring.set_password(self.SERVICE, self.AUDIT_KEY_ENTRY, raw)
+        else:
+            self._file_data[self.AUDIT_KEY_ENTRY] = {"key": raw}
+            self._write_file()
+        return bytes.fromhex(raw)
+
+
+def unlock_credential_store(store: CredentialStore) -> None:
+    """Unlock the encrypted-file backend from the environment or a prompt.
+    Cancelling leaves the store locked; credentials then stay empty."""
+    if not store.needs_unlock:
+        return
+    env = os.environ.get(CredentialStore.PASSPHRASE_ENV)
+    if env:
+        try:
+            store.unlock(env)
+            return
+        except ValueError:
+            print(f"{CredentialStore.PASSPHRASE_ENV} is wrong", file=sys.stderr)
+    prompt = (
+        "No OS keyring found. Choose a passphrase to encrypt stored credentials:"
+        if store.is_new
+        else "Passphrase for stored credentials:"
+    )
+    for _ in range(3):
+        passphrase, ok = QInputDialog.getText(
+            None, "Credentials", prompt, QLineEdit.EchoMode.Password
+        )
+        if not ok:
+            return
+        try:
+            store.unlock(passphrase)
+            return
+        except ValueError:
+            prompt = "Wrong passphrase, try again:"
 
-CONFIG = Config()
 
 # Strip ANSI color codes from Redis messages
 ANSI_RE = re.compile(r"\x1B\[[0-?]*[ -/]*[@-~]")
 
+# -----------------------------
+# Telemetry parsing (runs in the Redis thread)
+# -----------------------------
+# Match data with optional timestamp: "DEVICE [<t>] NAME: VAL" or "DEVICE <t> NAME:VAL"
+DATA_LOG_RE = re.compile(
+    r"^(?P<device>\S+)\s+(?:(?P<t>[+-]?\d+(?:\.\d+)?)\s+)?(?P<name>\S+):\s*(?P<val>[+-]?\d+(?:\.\d+)?)$"
+)
+# "DEVICE CONTROL NAME ACTION", or "DEVICE CONTROL NAME SET <value>" for analog
+CONTROL_LOG_RE = re.compile(
+    r"^(?P<device>\S+)\s+CONTROL\s+(?P<control>\S+)\s+(?P<action>\S+)"
+    r"(?:\s+(?P<value>[+-]?\d+(?:\.\d+)?))?$"
+)
+STATUS_LOG_RE = re.compile(r"^(?P<device>\S+):\s+STATUS\s+(?P<status>.+)$")
+
+
+class Sample(NamedTuple):
+    device: str
+    t: float
+    name: str
+    value: float
+
+
+class ControlEvent(NamedTuple):
+    device: str
+    control: str  # upper-cased
+    action: str  # upper-cased, e.g. "OPENED" or "SET"
+    value: Optional[float] = None  # reported position/setpoint of analog controls
+
+
+class StatusEvent(NamedTuple):
+    device: str
+    status: dict[str, Any]
+
+
+@dataclass
+class TelemetryBatch:
+    # Samples, control/status events and (str) non-sample lines for the operator
+    # log, in arrival order: a STATUS snapshot must not overtake a CONTROL echo
+    # that arrived before it
+    events: list[Sample | ControlEvent | StatusEvent | str] = field(
+        default_factory=list
+    )
+
+    @property
+    def samples(self) -> list[Sample]:
+        return [e for e in self.events if isinstance(e, Sample)]
+
+    @property
+    def controls(self) -> list[ControlEvent]:
+        return [e for e in self.events if isinstance(e, ControlEvent)]
+
+    @property
+    def statuses(self) -> list[StatusEvent]:
+        return [e for e in self.events if isinstance(e, StatusEvent)]
+
+    @property
+    def lines(self) -> list[str]:
+        return [e for e in self.events if isinstance(e, str)]
+
+    def __bool__(self) -> bool:
+        return bool(self.events)
+
+
+def parse_telemetry_line(line: str, batch: TelemetryBatch) -> None:
+    """Parse one text line from Redis and add the result to batch."""
+    m = line.split("]", 1)[-1]  # strip any leading timestamp
+    m = m.strip()
+
+    dataMatch = DATA_LOG_RE.match(m)
+    if dataMatch:
+        try:
+            t_str = dataMatch.group("t")
+            # Use arrival time if timestamp not provided
+            t = float(t_str) if t_str else time.time()
+            val = float(dataMatch.group("val"))
+        except ValueError:
+            return
+        if isfinite(t) and isfinite(val):
+            batch.events.append(
+                Sample(dataMatch.group("device"), t, dataMatch.group("name"), val)
+            )
+        return
+
+    batch.events.append(line)
+
+    controlMatch = CONTROL_LOG_RE.match(m)
+    if controlMatch:
+        value = controlMatch.group("value")
+        batch.events.append(
+            ControlEvent(
+                controlMatch.group("device"),
+                controlMatch.group("control").upper(),
+                controlMatch.group("action").upper(),
+                float(value) if value is not None else None,
+            )
+        )
+        return
+
+    statusMatch = STATUS_LOG_RE.match(m)
+    if statusMatch:
+        try:
+            status = json.loads(statusMatch.group("status"))
+        except ValueError:
+            batch.events.append(f"Unparseable STATUS payload: {m}")
+            return
+        if isinstance(status, dict):
+            batch.events.append(StatusEvent(statusMatch.group("device"), status))
+
+
+# JSON frames, one per message (or a JSON array of them), e.g.
+#   {"device": "ESP32-01", "t": 802.14, "values": {"LCFill": -0.22, "PTRun": 5.4}}
+#   {"device": "ESP32-01", "controls": {"AVFILL": "OPENED", "THROTTLE": 42.5}}
+#   {"device": "ESP32-01", "status": {"controls": {"AVFILL": "OPEN"}}}
+# "timestamp" is accepted for "t"; a frame may carry any mix of the three parts.
+def parse_telemetry_json(frame: Any, batch: TelemetryBatch) -> bool:
+    """Add a decoded JSON frame to batch; False if it is not in our schema."""
+    if isinstance(frame, list):
+        return all([parse_telemetry_json(f, batch) for f in frame]) and bool(frame)
+    if not isinstance(frame, dict) or not isinstance(frame.get("device"), str):
+        return False
+    device = frame["device"]
+    values = frame.get("values")
+    controls = frame.get("controls")
+    status = frame.get("status")
+    if not any(isinstance(p, dict) for p in (values, controls, status)):
+        return False
+
+    if isinstance(values, dict):
+        try:
+            t = float(frame.get("t", frame.get("timestamp", time.time())))
+        except (TypeError, ValueError):
+            t = float("nan")
+        if isfinite(t):
+            for name, raw in values.items():
+                try:
+                    val = float(raw)
+                except (TypeError, ValueError):
+                    continue
+                if isfinite(val):
+                    batch.events.append(Sample(device, t, str(name), val))
+
+    if isinstance(controls, dict) or isinstance(status, dict):
+        # Same log line the text format would have produced
+        shown = {k: v for k, v in frame.items() if k != "values"}
+        batch.events.append(json.dumps(shown, separators=(",", ":")))
+    if isinstance(controls, dict):
+        for control, action in controls.items():
+            if isinstance(action, (int, float)) and not isinstance(action, bool):
+                # Analog controls report their position as a number
+                batch.events.append(
+                    ControlEvent(device, str(control).upper(), "SET", float(action))
+                )
+                continue
+            batch.events.append(
+                ControlEvent(device, str(control).upper(), str(action).upper())
+            )
+    if isinstance(status, dict):
+        batch.events.append(StatusEvent(device, status))
+    return True
+
 
 class HttpRequestSignals(QObject):
+    sent = Signal()  # the request is going out now (not merely queued)
     success = Signal(object)
     error = Signal(str)
     finished = Signal()  # always emitted at the end of work (success or error)
@@ -118,9 +743,14 @@ class HttpRequestWorker(QRunnable):
         self.timeout = timeout
         self.auth = auth
         self.signals = HttpRequestSignals()
+        self.audit_id: Optional[int] = None  # seq of its audit "command" record
+        self.sent_at: Optional[float] = None  # time.monotonic() it went out
+        self.replied_at: Optional[float] = None  # ... and the reply (or error) came
 
     @Slot()
     def run(self) -> None:
+        self.sent_at = time.monotonic()
+        self.signals.sent.emit()
         try:
             client = self.get_client()
             # Override timeout and auth per-request
@@ -138,6 +768,7 @@ class HttpRequestWorker(QRunnable):
                     timeout=self.timeout,
                     auth=self.auth,
                 )
+            self.replied_at = time.monotonic()
 
             resp.raise_for_status()
             try:
@@ -146,16 +777,114 @@ class HttpRequestWorker(QRunnable):
                 payload = resp.text
             self.signals.success.emit(payload)
         except Exception as e:
+            if self.replied_at is None:
+                self.replied_at = time.monotonic()
             self.signals.error.emit(str(e))
         finally:
             # ensure cleanup hooks in the GUI always fire
             self.signals.finished.emit()
 
 
+class CommandPriority(IntEnum):
+    SAFING = 0  # abort / safing: jumps ahead of everything queued
+    NORMAL = 1
+
+
+class CommandDispatcher(QThread):
+    """Sends backend commands one at a time, in submission order.
+
+    SAFING commands are delivered before any queued NORMAL ones and may
+    cancel them outright (preempt=True). A request already in flight always
+    completes first; HTTP requests cannot be recalled."""
+
+    queueChanged = Signal(int, bool)  # (queued, in flight)
+
+    def __init__(self, parent: Optional[QObject] = None) -> None:
+        super().__init__(parent)
+        self._queue: queue.PriorityQueue = queue.PriorityQueue()
+        self._seq = itertools.count()
+        self._lock = threading.Lock()
+        self._in_flight = False
+        self._stop_flag = threading.Event()
+
+    @property
+    def depth(self) -> int:
+        return self._queue.qsize()
+
+    @property
+    def in_flight(self) -> bool:
+        return self._in_flight
+
+    def submit(
+        self,
+        worker: HttpRequestWorker,
+        priority: CommandPriority = CommandPriority.NORMAL,
+        preempt: bool = False,
+    ) -> None:
+        with self._lock:
+            if preempt:
+                self._cancel_below(priority)
+            self._queue.put((int(priority), next(self._seq), worker))
+        self.queueChanged.emit(self.depth, self._in_flight)
+
+    def _cancel_below(self, priority: CommandPriority) -> None:
+        kept = []
+        while True:
+            try:
+                item = self._queue.get_nowait()
+            except queue.Empty:
+                break
+            if item[0] > priority:
+                worker = item[2]
+                worker.signals.error.emit("cancelled: preempted by safing command")
+                worker.signals.finished.emit()
+            else:
+                kept.append(item)
+        for item in kept:
+            self._queue.put(item)
+
+    def run(self) -> None:
+        while not self._stop_flag.is_set():
+            with self._lock:
+                try:
+                    _prio, _seq, worker = self._queue.get_nowait()
+                except queue.Empty:
+                    worker = None
+                else:
+                    self._in_flight = True
+            if worker is None:
+                self._stop_flag.wait(0.02)
+                continue
+            self.queueChanged.emit(self.depth, True)
+            try:
+                worker.run()  # blocking; emits success/error/finished
+            finally:
+                self._in_flight = False
+                self.queueChanged.emit(self.depth, False)
+
+    def stop(self) -> None:
+        self._stop_flag.set()
+
+
 class RedisTailer(QThread):
-    message = Signal(str)
+    """Tails a Redis pub/sub channel or stream, reconnecting with exponential
+    backoff.
+
+    connectionState reports connecting/connected/reconnecting/stopped. After a
+    reconnect, gap(lost_at, restored_at) gives the wall-clock span during which
+    messages were missed. In stream mode reading resumes from the last seen
+    entry ID, so a gap is only reported if the stream was trimmed past it."""
+
+    batch = Signal(object)  # TelemetryBatch
     status = Signal(str)
     error = Signal(str)
+    connectionState = Signal(str)
+    gap = Signal(float, float)  # (lost_at, restored_at) as time.time()
+
+    BACKOFF_INITIAL_S = 0.5
+    BACKOFF_MAX_S = 10.0
+    # Parsed data is handed to the GUI at most this often
+    BATCH_INTERVAL_S = 0.05
 
     def __init__(
         self,
@@ -164,6 +893,8 @@ class RedisTailer(QThread):
         channel: str,
         username: str,
         password: str,
+        mode: str = "pubsub",
+        stream: str = "",
         parent: Optional[QObject] = None,
     ):
         super().__init__(parent)
@@ -172,98 +903,1334 @@ class RedisTailer(QThread):
         self.channel = channel
         self.username = username
         self.password = password
+        self.mode = mode
+        self.stream = stream
         self._stop_flag = threading.Event()
         self._pubsub = None
+        self._last_id: Optional[str] = None  # stream mode: last entry delivered
+        self._batch = TelemetryBatch()
+        self._last_emit = 0.0
+
+    def _connect(self):
+        import redis
+
+        # Add connection timeouts to prevent long hangs
+        # socket_connect_timeout: time to establish connection
+        # socket_timeout: time to receive data
+        # health_check_interval: ping an idle connection so a dead link is noticed
+        client = redis.Redis(
+            host=self.host,
+            port=self.port,
+            username=self.username,
+            password=self.password,
+            decode_responses=True,
+            socket_connect_timeout=2.0,  # 2 sec timeout to connect
+            socket_timeout=2.0,  # 2 sec timeout for operations
+            health_check_interval=5,
+            socket_keepalive=True,
+            socket_keepalive_options={
+                1: 1,  # TCP_KEEPIDLE: start after 1 second of inactivity
+                2: 1,  # TCP_KEEPINTVL: repeat every 1 second
+                3: 3,  # TCP_KEEPCNT: give up after 3 probes
+            }
+            if hasattr(socket, "TCP_KEEPIDLE")
+            else None,
+        )
+        # Test connection immediately
+        client.ping()
+        return client
 
     def run(self) -> None:
-        try:
-            import redis
-
-            # Add connection timeouts to prevent long hangs
-            # socket_connect_timeout: time to establish connection
-            # socket_timeout: time to receive data
-            # retry_on_timeout: retry on socket timeout
-            client = redis.Redis(
-                host=self.host,
-                port=self.port,
-                username=self.username,
-                password=self.password,
-                decode_responses=True,
-                socket_connect_timeout=2.0,  # 2 sec timeout to connect
-                socket_timeout=2.0,  # 2 sec timeout for operations
-                socket_keepalive=True,
-                socket_keepalive_options={
-                    1: 1,  # TCP_KEEPIDLE: start after 1 second of inactivity
-                    2: 1,  # TCP_KEEPINTVL: repeat every 1 second
-                    3: 3,  # TCP_KEEPCNT: give up after 3 probes
-                }
-                if hasattr(socket, "TCP_KEEPIDLE")
-                else None,
-            )
-            # Test connection immediately
-            client.ping()
-
-            self._pubsub = client.pubsub(ignore_subscribe_messages=True)
-            self._pubsub.subscribe(self.channel)
+        delay = self.BACKOFF_INITIAL_S
+        lost_at: Optional[float] = None
+        self.connectionState.emit("connecting")
+        while not self._stop_flag.is_set():
+            connected = False
+            try:
+                client = self._connect()
+                if self.mode == "stream":
+                    missed = self._resume_stream(client)
+                else:
+                    self._pubsub = client.pubsub(ignore_subscribe_messages=True)
+                    self._pubsub.subscribe(self.channel)
+                    missed = True
+                connected = True
+                self.connectionState.emit("connected")
+                if lost_at is not None and missed:
+                    self.gap.emit(lost_at, time.time())
+                lost_at = None
+                delay = self.BACKOFF_INITIAL_S
+                if self.mode == "stream":
+                    self._pump_stream(client)
+                else:
+                    self.status.emit(
+                        f"Subscribed to redis://{self.host}:{self.port} "
+                        f"channel '{self.channel}'"
+                    )
+                    self._pump()
+            except Exception as e:
+                if self._stop_flag.is_set():
+                    break
+                if connected:
+                    lost_at = time.time()
+                self.error.emit(str(e))
+                self.connectionState.emit("reconnecting")
+                self.status.emit(f"Redis reconnecting in {delay:.1f} s")
+                self._stop_flag.wait(delay)
+                delay = min(delay * 2, self.BACKOFF_MAX_S)
+            finally:
+                self._flush_batch()
+                try:
+                    if self._pubsub is not None:
+                        self._pubsub.close()
+                except Exception:
+                    pass
+                self._pubsub = None
+        self.connectionState.emit("stopped")
+        self.status.emit("Redis tailer stopped.")
+
+    @staticmethod
+    def _id_key(entry_id: str) -> tuple[int, int]:
+        ms, _, seq = entry_id.partition("-")
+        return int(ms), int(seq or 0)
+
+    def _resume_stream(self, client) -> bool:
+        """Pick the ID to read after; returns True if entries were lost because
+        the stream was trimmed past the last one we delivered."""
+        if self._last_id is None:
+            # First connect: start from the newest entry, no history
+            newest = client.xrevrange(self.stream, count=1)
+            self._last_id = newest[0][0] if newest else "0-0"
             self.status.emit(
-                f"Subscribed to redis://{self.host}:{self.port} channel '{self.channel}'"
+                f"Reading redis://{self.host}:{self.port} stream '{self.stream}'"
             )
+            return False
+        oldest = client.xrange(self.stream, count=1)
+        self.status.emit(
+            f"Resuming stream '{self.stream}' after {self._last_id} (replaying backlog)"
+        )
+        return bool(oldest) and self._id_key(oldest[0][0]) > self._id_key(
+            self._last_id
+        )
 
-            count = 0
-            while not self._stop_flag.is_set():
-                item = self._pubsub.get_message(timeout=0.1)  # 100ms, non-blocking
-                if not item:
-                    continue
-                if item.get("type") == "message":
-                    data = item.get("data")
-                    if not isinstance(data, str):
-                        try:
-                            data = json.dumps(data)
-                        except Exception:
-                            data = str(data)
-                    # optional: strip ANSI here if you've filled in the regex
-                    data = ANSI_RE.sub("", data)
-                    # emit per-line to be safe
-                    for line in str(data).splitlines():
-                        line = line.strip()
-                        if line:
-                            self.message.emit(line)
-
-                count += 1
-                if count % 100 == 0:
-                    QApplication.processEvents()  # keep GUI responsive
-
-        except Exception as e:
-            self.error.emit(str(e))
-        finally:
+    def _pump_stream(self, client) -> None:
+        """Forward stream entries until stop() or until the connection fails."""
+        while not self._stop_flag.is_set():
+            resp = client.xread({self.stream: self._last_id}, count=500, block=100)
+            for _stream, entries in resp or []:
+                for entry_id, entry in entries:
+                    self._last_id = entry_id
+                    if "data" in entry:
+                        data = entry["data"]
+                    elif len(entry) == 1:
+                        data = next(iter(entry.values()))
+                    else:
+                        data = json.dumps(entry)
+                    self._parse_data(data)
+            self._maybe_flush_batch()
+
+    def _pump(self) -> None:
+        """Forward messages until stop() or until the connection fails (raises)."""
+        while not self._stop_flag.is_set():
+            item = self._pubsub.get_message(timeout=0.02)
+            if item and item.get("type") == "message":
+                self._parse_data(item.get("data"))
+            self._maybe_flush_batch()
+
+    def _parse_data(self, data: Any) -> None:
+        if not isinstance(data, str):
             try:
-                if self._pubsub is not None:
-                    self._pubsub.close()
+                data = json.dumps(data)
             except Exception:
-                pass
-            self.status.emit("Redis tailer stopped.")
+                data = str(data)
+        # optional: strip ANSI here if you've filled in the regex
+        data = ANSI_RE.sub("", data)
+        if data.lstrip()[:1] in ("{", "["):
+            try:
+                frame = json.loads(data)
+            except ValueError:
+                pass  # not JSON after all; fall through to the text formats
+            else:
+                if not parse_telemetry_json(frame, self._batch):
+                    self._batch.events.append(f"Unrecognised JSON message: {data}")
+                return
+        # parse per-line to be safe
+        for line in str(data).splitlines():
+            line = line.strip()
+            if line:
+                parse_telemetry_line(line, self._batch)
+
+    def _maybe_flush_batch(self) -> None:
+        if time.monotonic() - self._last_emit >= self.BATCH_INTERVAL_S:
+            self._flush_batch()
+
+    def _flush_batch(self) -> None:
+        if self._batch:
+            self.batch.emit(self._batch)
+            self._batch = TelemetryBatch()
+        self._last_emit = time.monotonic()
 
     def stop(self) -> None:
         self._stop_flag.set()
 
 
-class keySwitchMonitor(QThread):
+# -----------------------------
+# Control authority lease
+# -----------------------------
+# The lease key holds the holder's JSON identity and expires after the TTL
+# unless renewed. Hand-over: a requester writes its identity to <key>:request;
+# the holder approves by swapping the lease value to it in one step, so no
+# third instance can grab control in between.
+LEASE_RENEW = """
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"""
+LEASE_RELEASE = """
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+"""
+LEASE_TRANSFER = """
+if redis.call('GET', KEYS[1]) == ARGV[1]
+    and redis.call('GET', KEYS[2]) == ARGV[2] then
+  redis.call('SET', KEYS[1], ARGV[2], 'PX', ARGV[3])
+  redis.call('DEL', KEYS[2])
+  return 1
+end
+return 0
+"""
+
+
+def describe_lease_holder(raw: Optional[str]) -> str:
+    """'operator@host' for a lease/request value, '' if none."""
+    if not raw:
+        return ""
+    try:
+        ident = json.loads(raw)
+        return f"{ident.get('operator') or ident.get('os_user')}@{ident.get('host')}"
+    except (ValueError, AttributeError):
+        return "unknown"
+
+
+class ControlLease(QThread):
+    """Holds, renews and hands over the control authority lease in Redis.
+    Any Redis error counts as not holding the lease (fail-safe)."""
+
+    leaseChanged = Signal(bool, str)  # (we hold it, holder description or "")
+    handoverRequested = Signal(str, str)  # (requester description, request value)
+    message = Signal(str)
+    error = Signal(str)
+
+    REQUEST_TTL_S = 30.0
+
+    def __init__(
+        self,
+        host: str,
+        port: int,
+        username: str,
+        password: str,
+        key: str,
+        ttl_s: float,
+        parent: Optional[QObject] = None,
+    ) -> None:
+        super().__init__(parent)
+        self.host = host
+        self.port = port
+        self.username = username
+        self.password = password
+        self.key = key
+        self.request_key = f"{key}:request"
+        self.ttl_ms = int(ttl_s * 1000)
+        self.heartbeat_s = ttl_s / 3
+        self.instance_id = uuid.uuid4().hex
+        self.operator = ""  # set by the GUI once the role is known
+        self._value: Optional[str] = None  # our lease value while holding
+        self._held = False
+        self._holder = ""
+        self._requested = False
+        self._seen_request: Optional[str] = None
+        self._actions: queue.Queue[tuple[str, str]] = queue.Queue()
+        self._stop_flag = threading.Event()
+
+    # Called from the GUI thread; performed on the next heartbeat
+    def acquire(self) -> None:
+        self._actions.put(("acquire", ""))
+
+    def release(self) -> None:
+        self._actions.put(("release", ""))
+
+    def request(self) -> None:
+        self._actions.put(("request", ""))
+
+    def grant(self, request_value: str) -> None:
+        self._actions.put(("grant", request_value))
+
+    def deny(self, request_value: str) -> None:
+        self._actions.put(("deny", request_value))
+
+    def stop(self) -> None:
+        self._actions.put(("release", ""))
+        self._stop_flag.set()
+
+    def _identity(self) -> str:
+        return json.dumps(
+            {
+                "id": self.instance_id,
+                "operator": self.operator,
+                "os_user": getpass.getuser(),
+                "host": socket.gethostname(),
+                "since": datetime.now().isoformat(timespec="seconds"),
+            },
+            sort_keys=True,
+        )
+
+    def _owner_id(self, raw: Optional[str]) -> Optional[str]:
+        try:
+            return json.loads(raw).get("id") if raw else None
+        except (ValueError, AttributeError):
+            return None
+
+    def _connect(self):
+        import redis
+
+        client = redis.Redis(
+            host=self.host,
+            port=self.port,
+            username=self.username,
+            password=self.password,
+            decode_responses=True,
+            socket_connect_timeout=2.0,
+            socket_timeout=2.0,
+        )
+        client.ping()
+        return client
+
+    def run(self) -> None:
+        client = None
+        while True:
+            try:
+                if client is None:
+                    client = self._connect()
+                self._run_actions(client)
+                if self._stop_flag.is_set():
+                    break
+                self._heartbeat(client)
+            except Exception as e:
+                if self._stop_flag.is_set():
+                    break
+                client = None
+                self.error.emit(str(e))
+                self._value = None
+                self._set_state(False, "")
+            self._stop_flag.wait(self.heartbeat_s)
+        self._set_state(False, "")
+
+    def _run_actions(self, client) -> None:
+        while True:
+            try:
+                action, arg = self._actions.get_nowait()
+            except queue.Empty:
+                return
+            if action == "acquire":
+                value = self._identity()
+                if client.set(self.key, value, nx=True, px=self.ttl_ms):
+                    self._value = value
+                    self.message.emit("Control lease acquired")
+                else:
+                    holder = describe_lease_holder(client.get(self.key))
+                    self.message.emit(f"Control lease is held by {holder}")
+            elif action == "release" and self._value is not None:
+                client.eval(LEASE_RELEASE, 1, self.key, self._value)
+                self._value = None
+                self.message.emit("Control lease released")
+            elif action == "request":
+                value = self._identity()
+                client.set(self.request_key, value, px=int(self.REQUEST_TTL_S * 1000))
+                self._requested = True
+                self.message.emit(f"Requested control from {self._holder}")
+            elif action == "grant" and self._value is not None:
+                ok = client.eval(
+                    LEASE_TRANSFER,
+                    2,
+                    self.key,
+                    self.request_key,
+                    self._value,
+                    arg,
+                    self.ttl_ms,
+                )
+                if ok:
+                    self._value = None
+                    self.message.emit(
+                        f"Control handed over to {describe_lease_holder(arg)}"
+                    )
+                else:
+                    self.message.emit("Hand-over failed: request expired or withdrawn")
+            elif action == "deny":
+                client.eval(LEASE_RELEASE, 1, self.request_key, arg)
+                who = describe_lease_holder(arg)
+                self.message.emit(f"Declined control request from {who}")
+
+    def _heartbeat(self, client) -> None:
+        raw = client.get(self.key)
+        if raw is not None and self._owner_id(raw) == self.instance_id:
+            if self._value != raw:
+                self._value = raw  # handed over to us
+                self._requested = False
+                self.message.emit("Control lease handed over to this station")
+            client.eval(LEASE_RENEW, 1, self.key, raw, self.ttl_ms)
+            self._set_state(True, describe_lease_holder(raw))
+            request = client.get(self.request_key)
+            if request and request != self._seen_request:
+                self._seen_request = request
+                self.handoverRequested.emit(describe_lease_holder(request), request)
+            return
+
+        if self._value is not None:
+            self.message.emit("Control lease lost (expired or taken)")
+        self._value = None
+        if self._requested:
+            request = client.get(self.request_key)
+            if self._owner_id(request) != self.instance_id:
+                self._requested = False
+                self.message.emit("Control request declined or expired")
+        self._set_state(False, describe_lease_holder(raw))
+
+    def _set_state(self, held: bool, holder: str) -> None:
+        if (held, holder) != (self._held, self._holder):
+            self._held = held
+            self._holder = holder
+            self.leaseChanged.emit(held, holder)
+
+
+# -----------------------------
+# Hardware panel serial framing
+# -----------------------------
+# One frame per line: $<TYPE>[,<field>...]*<XX>\n where XX is the hex XOR of
+# every byte between '$' and '*' (NMEA style). Types from the panel:
+#   KEY,<0|1>    key switch (1 = closed/armed)
+#   ARM,<0|1>    arm switch
+#   ABORT,<0|1>  abort button (1 = pressed)
+#   HB,<uptime>  heartbeat, sent periodically even when nothing changes
+# Lines without a valid checksum are dropped; old firmware that sends a bare
+# "0"/"1" must be updated, since a single noise byte could otherwise arm.
+# To the panel, OUT,<name>,<0|1> drives an LED or the buzzer (PANEL_OUTPUTS).
+# The full output state is re-sent every second, so the panel can treat
+# silence from the host as a lost link.
+PANEL_OUTPUTS = ("ARMED", "STREAMING", "ALARM", "LINK_LOST", "BUZZER")
+
+
+class FrameError(ValueError):
+    pass
+
+
+class PanelFrame(NamedTuple):
+    type: str
+    fields: list[str]
+
+
+def panel_checksum(body: str) -> str:
+    cs = 0
+    for b in body.encode("ascii"):
+        cs ^= b
+    return f"{cs:02X}"
+
+
+def encode_panel_frame(msg_type: str, *fields: object) -> bytes:
+    body = ",".join([msg_type, *(str(f) for f in fields)])
+    return f"${body}*{panel_checksum(body)}\n".encode("ascii")
+
+
+def decode_panel_frame(raw: bytes) -> PanelFrame:
+    try:
+        line = raw.decode("ascii").strip()
+    except UnicodeDecodeError:
+        raise FrameError("non-ASCII bytes") from None
+    if not line.startswith("$"):
+        raise FrameError(f"no start marker: {line[:20]!r}")
+    body, sep, cs = line[1:].rpartition("*")
+    if not sep:
+        raise FrameError(f"no checksum: {line[:20]!r}")
+    if cs.upper() != panel_checksum(body):
+        raise FrameError(f"checksum mismatch: {line[:20]!r}")
+    msg_type, *fields = body.split(",")
+    if not msg_type:
+        raise FrameError("empty message type")
+    return PanelFrame(msg_type.upper(), fields)
+
+
+class HardwarePanel(QThread):
+    """Driver for the ESP32 control panel: key switch, arm switch and abort
+    button in, status LEDs and buzzer out.
+
+    With no fixed port, the adapter is found by USB serial number or VID:PID,
+    and the driver keeps retrying so the adapter can be unplugged and replugged."""
+
     statusSig = Signal(int)
     messageSig = Signal(str)
+    connectionSig = Signal(bool, str)  # (connected, port or search description)
+    watchdogSig = Signal(bool)  # True: no valid reading within timeout_s
+    inputSig = Signal(str, int)  # other panel inputs (ARM, ABORT, ...) on change
+
+    RETRY_S = 1.0
+    MAX_LINE = 128
+    OUTPUT_REFRESH_S = 1.0
+    BAD_FRAME_LOG_S = 5.0  # at most one bad-frame report per this many seconds
 
-    def __init__(self, port: str):
+    def __init__(
+        self,
+        port: str = "",
+        usb_ids: Optional[list[str]] = None,
+        serial_number: str = "",
+        timeout_s: float = 0.0,
+    ):
         super().__init__()
+        self.fixed_port = port
+        self.usb_ids = {i.upper() for i in (usb_ids or [])}
+        self.serial_number = serial_number
+        self.port: Optional[str] = None
+        self.espSerial: Optional[serial.Serial] = None
+        self._stop_flag = threading.Event()
         self.status: int | None = None  # Open is 0, 1 is closed
-        self.espSerial = serial.Serial(port)
+        # The firmware repeats its reading; any valid one counts as a heartbeat
+        self.timeout_s = timeout_s
+        self._last_valid = time.monotonic()
+        self.expired = False
+        self.inputs: dict[str, int] = {}
+        self.good_frames = 0
+        self.bad_frames = 0
+        self._bad_since_report = 0
+        self._last_bad_report = 0.0
+        # Written by the GUI thread, sent by this thread
+        self._outputs: dict[str, int] = {name: 0 for name in PANEL_OUTPUTS}
+        self._outputs_lock = threading.Lock()
+        self._outputs_dirty = True
+        self._last_output_send = 0.0
+
+    def set_output(self, name: str, on: bool) -> None:
+        """Request an LED/buzzer state; sent as soon as the link is up."""
+        with self._outputs_lock:
+            value = int(bool(on))
+            if self._outputs.get(name) != value:
+                self._outputs[name] = value
+                self._outputs_dirty = True
+
+    def _send_outputs(self) -> None:
+        now = time.monotonic()
+        with self._outputs_lock:
+            if not self._outputs_dirty and now - self._last_output_send < (
+                self.OUTPUT_REFRESH_S
+            ):
+                return
+            outputs = dict(self._outputs)
+            self._outputs_dirty = False
+        for name, value in outputs.items():
+            self.espSerial.write(encode_panel_frame("OUT", name, value))
+        self._last_output_send = now
+
+    def find_port(self) -> Optional[str]:
+        """Configured port, else the first adapter matching serial number / VID:PID."""
+        if self.fixed_port:
+            return self.fixed_port
+        from serial.tools import list_ports
+
+        for info in list_ports.comports():
+            if self.serial_number:
+                if info.serial_number == self.serial_number:
+                    return info.device
+                continue
+            if info.vid is not None and info.pid is not None:
+                if f"{info.vid:04X}:{info.pid:04X}" in self.usb_ids:
+                    return info.device
+        return None
+
+    def _check_watchdog(self) -> None:
+        if self.timeout_s <= 0 or self.expired:
+            return
+        if time.monotonic() - self._last_valid > self.timeout_s:
+            self.expired = True
+            # Whatever arrives next is reported, even if it equals the old state
+            self.status = None
+            self.watchdogSig.emit(True)
 
     def run(self):
-        while True:
-            line = self.espSerial.readline(3).strip()
-            stat = int(line)
+        self._last_valid = time.monotonic()
+        searching_reported = False
+        while not self._stop_flag.is_set():
+            self._check_watchdog()
+            port = self.find_port()
+            if port is None:
+                if not searching_reported:
+                    wanted = self.serial_number or ", ".join(sorted(self.usb_ids))
+                    self.connectionSig.emit(False, f"searching for {wanted}")
+                    searching_reported = True
+                self._stop_flag.wait(self.RETRY_S)
+                continue
+            try:
+                # Short read timeout so the loop can send outputs and notice stop()
+                self.espSerial = serial.Serial(port, timeout=0.1, write_timeout=0.5)
+            except serial.SerialException as e:
+                if not searching_reported:
+                    self.messageSig.emit(f"Panel: cannot open {port}: {e}")
+                    self.connectionSig.emit(False, port)
+                    searching_reported = True
+                self._stop_flag.wait(self.RETRY_S)
+                continue
+
+            self.port = port
+            searching_reported = False
+            self._last_output_send = 0.0  # push the full output state right away
+            self.connectionSig.emit(True, port)
+            try:
+                self._read_loop()
+            except (serial.SerialException, OSError) as e:
+                self.messageSig.emit(f"Panel disconnected from {port}: {e}")
+            finally:
+                self.espSerial.close()
+                self.espSerial = None
+                self.port = None
+                # Next reading after a reconnect is reported even if unchanged
+                self.status = None
+                self.connectionSig.emit(False, port)
+
+    def _read_loop(self) -> None:
+        while not self._stop_flag.is_set():
+            self._check_watchdog()
+            self._send_outputs()
+            raw = self.espSerial.readline(self.MAX_LINE)
+            if not raw.strip():
+                continue
+            try:
+                frame = decode_panel_frame(raw)
+                self._handle_frame(frame)
+            except FrameError as e:
+                self._bad_frame(str(e))
+                continue
 
-            if stat != self.status and stat in [0, 1]:
-                self.status = stat
+            self.good_frames += 1
+            self._last_valid = time.monotonic()
+            if self.expired:
+                self.expired = False
+                self.watchdogSig.emit(False)
+
+    def _handle_frame(self, frame: PanelFrame) -> None:
+        """Act on a decoded frame; FrameError if its fields are invalid."""
+        if frame.type == "HB":
+            return
+        if len(frame.fields) != 1 or frame.fields[0] not in ("0", "1"):
+            raise FrameError(f"bad {frame.type} fields: {frame.fields}")
+        value = int(frame.fields[0])
+        if frame.type == "KEY":
+            if value != self.status:
+                self.status = value
                 self.statusSig.emit(self.status)
+        elif self.inputs.get(frame.type) != value:
+            self.inputs[frame.type] = value
+            self.inputSig.emit(frame.type, value)
+
+    def _bad_frame(self, reason: str) -> None:
+        self.bad_frames += 1
+        self._bad_since_report += 1
+        now = time.monotonic()
+        if now - self._last_bad_report >= self.BAD_FRAME_LOG_S:
+            self.messageSig.emit(
+                f"Panel: {self._bad_since_report} bad frame(s) "
+                f"({self.bad_frames} total), last: {reason}"
+            )
+            self._bad_since_report = 0
+            self._last_bad_report = now
+
+    def stop(self) -> None:
+        self._stop_flag.set()
+
+
+# -----------------------------
+# Command acknowledgement tracking
+# -----------------------------
+# Commanded action -> state the hardware reports once it has moved
+EXPECTED_STATE = {"OPEN": "OPEN", "CLOSE": "CLOSED"}
+# Reported CONTROL/STATUS words -> normalized state
+REPORTED_STATE = {
+    "OPEN": "OPEN",
+    "OPENED": "OPEN",
+    "CLOSE": "CLOSED",
+    "CLOSED": "CLOSED",
+}
+
+
+@dataclass
+class ControlState:
+    """What the GUI knows about one control, for its state indicator."""
+
+    commanded: str = ""  # state the last command asked for ("" = none yet)
+    reported: str = ""  # last state reported by the hardware ("" = never)
+    reported_at: float = 0.0  # time.monotonic() of that report
+    tolerance: Optional[float] = None  # analog: compare numerically within this
+
+
+def states_match(expected: str, reported: str, tolerance: Optional[float]) -> bool:
+    """Reported state confirms the expected one: exact for OPEN/CLOSED, within
+    tolerance for analog positions."""
+    if tolerance is None:
+        return reported == expected
+    try:
+        return abs(float(reported) - float(expected)) <= tolerance
+    except ValueError:
+        return False
+
+
+def control_indicator(state: ControlState, pending: bool) -> tuple[str, str]:
+    """(text, color) for a control's state indicator."""
+    if pending:
+        return "MOVING", "#b58900"
+    if state.commanded and not states_match(
+        state.commanded, state.reported, state.tolerance
+    ):
+        return "MISMATCH", "#c62828"  # unconfirmed, or moved on its own
+    if not state.reported:
+        return "UNKNOWN", "#757575"
+    if state.commanded:
+        return state.reported, "#2e7d32"  # confirmed the last command
+    return state.reported, "#1565c0"  # reported, but not commanded this session
+
+
+@dataclass
+class PendingCommand:
+    control: str
+    expected: str  # normalized state, e.g. "OPEN", or an analog setpoint
+    sent_at: float  # time.monotonic()
+    tolerance: Optional[float] = None  # analog: see states_match
+
+
+class CommandTracker(QObject):
+    """Tracks each valve command until the matching CONTROL or STATUS report
+    arrives, and flags it once the timeout passes without one."""
+
+    confirmed = Signal(str, str, float)  # (control, state, latency s)
+    unconfirmed = Signal(str, str, str)  # (control, expected state, reason)
+
+    def __init__(self, timeout_s: float, parent: Optional[QObject] = None) -> None:
+        super().__init__(parent)
+        self.timeout_s = timeout_s
+        self._pending: dict[str, PendingCommand] = {}
+        self._timer = QTimer(self)
+        self._timer.setInterval(100)
+        self._timer.timeout.connect(self._check_timeouts)
+        self._timer.start()
+
+    def track(
+        self, control: str, expected: str, tolerance: Optional[float] = None
+    ) -> None:
+        # A newer command for the same control supersedes the old one
+        self._pending[control] = PendingCommand(
+            control, expected, time.monotonic(), tolerance
+        )
+
+    def is_pending(self, control: str) -> bool:
+        return control in self._pending
+
+    def report(self, control: str, state: str) -> None:
+        """Feed a state reported by the hardware; confirms a matching command."""
+        cmd = self._pending.get(control)
+        if cmd is None or not states_match(cmd.expected, state, cmd.tolerance):
+            return
+        del self._pending[control]
+        self.confirmed.emit(control, state, time.monotonic() - cmd.sent_at)
+
+    def fail(self, control: str, reason: str) -> None:
+        cmd = self._pending.pop(control, None)
+        if cmd is not None:
+            self.unconfirmed.emit(control, cmd.expected, reason)
+
+    def clear(self) -> None:
+        self._pending.clear()
+
+    @Slot()
+    def _check_timeouts(self) -> None:
+        now = time.monotonic()
+        for control, cmd in list(self._pending.items()):
+            if now - cmd.sent_at > self.timeout_s:
+                del self._pending[control]
+                self.unconfirmed.emit(
+                    control,
+                    cmd.expected,
+                    f"no confirmation within {self.timeout_s:g} s",
+                )
+
+
+# -----------------------------
+# Abort / safing sequence
+# -----------------------------
+class AbortSequence(QObject):
+    """Runs configured safing steps in order. Commands go out back to back;
+    delay steps pause the sequence without blocking the GUI."""
+
+    stepStarted = Signal(int, int, str)  # (step number, total, description)
+    finished = Signal()
+
+    def __init__(
+        self,
+        steps: list[dict[str, Any]],
+        send: Callable[[str, str], None],
+        parent: Optional[QObject] = None,
+    ) -> None:
+        super().__init__(parent)
+        self.steps = steps
+        self._send = send
+        self._index = 0
+        self.running = False
+        self._timer = QTimer(self)
+        self._timer.setSingleShot(True)
+        self._timer.timeout.connect(self._run_steps)
+
+    def start(self) -> None:
+        self._timer.stop()
+        self._index = 0
+        self.running = True
+        self._run_steps()
+
+    @Slot()
+    def _run_steps(self) -> None:
+        total = len(self.steps)
+        while self._index < total:
+            step = self.steps[self._index]
+            self._index += 1
+            if "delay_s" in step:
+                delay = float(step["delay_s"])
+                self.stepStarted.emit(self._index, total, f"wait {delay:g} s")
+                self._timer.start(int(delay * 1000))
+                return
+            control = str(step["control"]).upper()
+            action = str(step["action"]).upper()
+            self.stepStarted.emit(self._index, total, f"{action} {control}")
+            self._send(control, action)
+        self.running = False
+        self.finished.emit()
+
+
+# -----------------------------
+# Sensor conditions (shared by the sequencer and interlocks)
+# -----------------------------
+COMPARE_OPS: dict[str, Callable[[float, float], bool]] = {
+    ">": operator.gt,
+    ">=": operator.ge,
+    "<": operator.lt,
+    "<=": operator.le,
+    "==": operator.eq,
+    "!=": operator.ne,
+}
+
+
+@dataclass
+class Condition:
+    sensor: str  # "NAME" (any device) or "DEVICE:NAME"
+    op: str
+    value: float
+
+    @classmethod
+    def from_dict(cls, d: Any) -> Condition:
+        if not isinstance(d, dict):
+            raise ValueError("condition must be an object")
+        op = str(d.get("op", ""))
+        if op not in COMPARE_OPS:
+            raise ValueError(f"unknown comparison '{op}'")
+        sensor = d.get("sensor")
+        if not isinstance(sensor, str) or not sensor:
+            raise ValueError("condition needs a sensor")
+        return cls(sensor, op, float(d["value"]))
+
+    def evaluate(self, lookup: Callable[[str], Optional[float]]) -> Optional[bool]:
+        """True/False, or None if the sensor has no recent reading."""
+        current = lookup(self.sensor)
+        if current is None:
+            return None
+        return COMPARE_OPS[self.op](current, self.value)
+
+    def __str__(self) -> str:
+        return f"{self.sensor} {self.op} {self.value:g}"
+
+
+# -----------------------------
+# Redlines and interlocks
+# -----------------------------
+@dataclass
+class Redline:
+    name: str
+    condition: Condition
+    duration_s: float  # condition must hold this long (sample time) to trip
+    action: str  # "abort" | "alarm"
+
+    @classmethod
+    def from_dict(cls, d: Any) -> Redline:
+        cond = Condition.from_dict(d)
+        duration_s = float(d.get("duration_ms", 0)) / 1000.0
+        if duration_s < 0:
+            raise ValueError("duration_ms must be >= 0")
+        action = str(d.get("action", "alarm")).lower()
+        if action not in {"abort", "alarm"}:
+            raise ValueError("action must be abort or alarm")
+        return cls(str(d.get("name") or cond), cond, duration_s, action)
+
+
+@dataclass
+class Interlock:
+    control: str
+    action: str
+    condition: Condition
+
+    @classmethod
+    def from_dict(cls, d: Any) -> Interlock:
+        cond = Condition.from_dict(d)
+        action = str(d.get("action", "")).upper()
+        if action not in {"OPEN", "CLOSE", "SET"}:
+            raise ValueError("action must be OPEN, CLOSE or SET")
+        control = d.get("control")
+        if not isinstance(control, str) or not control:
+            raise ValueError("interlock needs a control")
+        return cls(control.upper(), action, cond)
+
+
+def check_redlines(rules: Any) -> list[dict[str, Any]]:
+    if not isinstance(rules, list):
+        raise ValueError("redlines must be a list")
+    for i, rule in enumerate(rules, 1):
+        try:
+            Redline.from_dict(rule)
+        except (KeyError, TypeError, ValueError) as e:
+            raise ValueError(f"redline {i}: {e}") from None
+    return rules
+
+
+def check_interlocks(rules: Any) -> list[dict[str, Any]]:
+    if not isinstance(rules, list):
+        raise ValueError("interlocks must be a list")
+    for i, rule in enumerate(rules, 1):
+        try:
+            Interlock.from_dict(rule)
+        except (KeyError, TypeError, ValueError) as e:
+            raise ValueError(f"interlock {i}: {e}") from None
+    return rules
+
+
+def check_go_conditions(conditions: Any) -> list[dict[str, Any]]:
+    if not isinstance(conditions, list):
+        raise ValueError("go_conditions must be a list")
+    for i, cond in enumerate(conditions, 1):
+        try:
+            Condition.from_dict(cond)
+        except (KeyError, TypeError, ValueError) as e:
+            raise ValueError(f"go condition {i}: {e}") from None
+    return conditions
+
+
+class RedlineMonitor:
+    """Evaluates redlines per sample. A rule trips once when its condition has
+    held for duration_ms on one series, and re-arms when the value recovers."""
+
+    def __init__(self, rules: list[dict[str, Any]]) -> None:
+        self.by_sensor: dict[str, list[Redline]] = {}
+        for rule in map(Redline.from_dict, rules):
+            self.by_sensor.setdefault(rule.condition.sensor, []).append(rule)
+        self._since: dict[tuple[int, str], float] = {}  # (rule, series) -> t
+        self._tripped: set[tuple[int, str]] = set()
+
+    def evaluate(self, series: str, name: str, t: float, value: float) -> list[Redline]:
+        if not self.by_sensor or not isfinite(value):
+            return []
+        fired: list[Redline] = []
+        for rule in self.by_sensor.get(series, []) + self.by_sensor.get(name, []):
+            key = (id(rule), series)
+            if not rule.condition.evaluate(lambda _s: value):
+                self._since.pop(key, None)
+                self._tripped.discard(key)
+                continue
+            start = self._since.setdefault(key, t)
+            if key not in self._tripped and t - start >= rule.duration_s:
+                self._tripped.add(key)
+                fired.append(rule)
+        return fired
+
+
+def blocking_interlock(
+    interlocks: list[Interlock],
+    control: str,
+    action: str,
+    lookup: Callable[[str], Optional[float]],
+) -> Optional[str]:
+    """Reason the command is interlocked, or None if it may be sent.
+    A sensor with no recent reading (lookup gives None) counts as blocking."""
+    for lock in interlocks:
+        if (lock.control, lock.action) != (control, action):
+            continue
+        result = lock.condition.evaluate(lookup)
+        if result is None:
+            return f"no recent data for {lock.condition.sensor}"
+        if result:
+            current = lookup(lock.condition.sensor)
+            return f"{lock.condition} (now {current:g})"
+    return None
+
+
+# -----------------------------
+# Test sequencer
+# -----------------------------
+# Procedure file (JSON, or YAML if PyYAML is installed):
+#   {"name": "Cold flow 1", "steps": [
+#     {"type": "command", "control": "AVFILL", "action": "OPEN"},
+#     {"type": "wait", "seconds": 2.0},
+#     {"type": "wait_until", "sensor": "PTN2OSupply", "op": ">", "value": 700,
+#      "timeout_s": 60, "on_timeout": "hold"},       # or "abort" / "continue"
+#     {"type": "hold", "message": "Confirm pad clear"},
+#     {"type": "log", "message": "Fill complete"}]}
+# Any step may carry "when": {"sensor", "op", "value"}; it is skipped if false.
+SEQUENCE_STEP_TYPES = {"command", "wait", "wait_until", "hold", "log"}
+
+
+def load_procedure(path: Path) -> tuple[str, list[dict[str, Any]]]:
+    """Read and validate a procedure file; ValueError on any problem."""
+    text = path.read_text(encoding="utf-8")
+    if path.suffix.lower() in (".yaml", ".yml"):
+        try:
+            import yaml
+        except ImportError:
+            raise ValueError("PyYAML is not installed; use a JSON procedure")
+        data = yaml.safe_load(text)
+    else:
+        data = json.loads(text)
+    if not isinstance(data, dict) or not isinstance(data.get("steps"), list):
+        raise ValueError("procedure must be an object with a 'steps' list")
+    steps = check_procedure_steps(data["steps"])
+    return str(data.get("name") or path.stem), steps
+
+
+def check_procedure_steps(steps: Any) -> list[dict[str, Any]]:
+    """Validate sequencer steps (see the procedure format above)."""
+    if not isinstance(steps, list):
+        raise ValueError("steps must be a list")
+    for i, step in enumerate(steps, 1):
+        if not isinstance(step, dict) or step.get("type") not in SEQUENCE_STEP_TYPES:
+            raise ValueError(f"step {i}: type must be one of {SEQUENCE_STEP_TYPES}")
+        try:
+            kind = step["type"]
+            if kind == "command":
+                if str(step["action"]).upper() not in {"OPEN", "CLOSE"}:
+                    raise ValueError("action must be OPEN or CLOSE")
+                str(step["control"])
+            elif kind == "wait":
+                if float(step["seconds"]) < 0:
+                    raise ValueError("seconds must be >= 0")
+            elif kind == "wait_until":
+                Condition.from_dict(step)
+                float(step.get("timeout_s", 0))
+                if step.get("on_timeout", "hold") not in {"hold", "abort", "continue"}:
+                    raise ValueError("on_timeout must be hold, abort or continue")
+            if "when" in step:
+                Condition.from_dict(step["when"])
+        except (KeyError, TypeError, ValueError) as e:
+            raise ValueError(f"step {i}: {e}") from None
+    return steps
+
+
+def describe_step(step: dict[str, Any]) -> str:
+    kind = step["type"]
+    if kind == "command":
+        text = f"{str(step['action']).upper()} {str(step['control']).upper()}"
+    elif kind == "wait":
+        text = f"Wait {float(step['seconds']):g} s"
+    elif kind == "wait_until":
+        text = f"Wait until {Condition.from_dict(step)}"
+        if float(step.get("timeout_s", 0)) > 0:
+            text += f" (timeout {float(step['timeout_s']):g} s)"
+    elif kind == "hold":
+        text = f"HOLD: {step.get('message', 'operator hold')}"
+    else:
+        text = f"Log: {step.get('message', '')}"
+    if "when" in step:
+        text += f"  [if {Condition.from_dict(step['when'])}]"
+    return text
+
+
+class Sequencer(QObject):
+    """Steps through a procedure on a timer. Commands go through the given
+    send callable; sensor conditions read the latest values via lookup."""
+
+    loaded = Signal(str)  # procedure name
+    stepChanged = Signal(int)  # index of the current step (-1 when idle)
+    stateChanged = Signal(str)  # idle | running | holding | done | aborted
+    message = Signal(str)
+    abortRequested = Signal(str)
+
+    TICK_MS = 50
+
+    def __init__(
+        self,
+        send: Callable[[str, str], bool],
+        lookup: Callable[[str], Optional[float]],
+        can_command: Callable[[], bool],
+        parent: Optional[QObject] = None,
+    ) -> None:
+        super().__init__(parent)
+        self._send = send
+        self._lookup = lookup
+        self._can_command = can_command
+        self.name = ""
+        self.steps: list[dict[str, Any]] = []
+        self.index = -1
+        self.state = "idle"
+        self._step_started = 0.0  # monotonic time the current step began
+        self._held_at: Optional[float] = None
+        self._waiting_for_data = False  # wait_until sensor has no recent data
+        self._timer = QTimer(self)
+        self._timer.setInterval(self.TICK_MS)
+        self._timer.timeout.connect(self._tick)
+
+    def load(self, name: str, steps: list[dict[str, Any]]) -> None:
+        if self.state in ("running", "holding"):
+            raise RuntimeError("sequence is in progress")
+        self.name = name
+        self.steps = steps
+        self.loaded.emit(name)
+        self._set_index(-1)
+        self._set_state("idle")
+
+    def start(self) -> None:
+        if not self.steps or self.state in ("running", "holding"):
+            return
+        self.message.emit(f"Sequence '{self.name}' started")
+        self._set_state("running")
+        self._enter_step(0)
+        self._timer.start()
+
+    def hold(self, reason: str = "operator hold") -> None:
+        if self.state != "running":
+            return
+        self._held_at = time.monotonic()
+        self._set_state("holding")
+        self.message.emit(f"HOLD at step {self.index + 1}: {reason}")
+
+    def resume(self) -> None:
+        if self.state != "holding":
+            return
+        step = self.steps[self.index]
+        if step["type"] == "hold":
+            self.message.emit(f"Hold point {self.index + 1} released")
+            self._set_state("running")
+            self._enter_step(self.index + 1)
+            return
+        # Time spent holding does not count towards waits/timeouts
+        if self._held_at is not None:
+            self._step_started += time.monotonic() - self._held_at
+        self._held_at = None
+        self._set_state("running")
+        self.message.emit(f"Resumed at step {self.index + 1}")
+
+    def abort(self, reason: str = "operator abort") -> None:
+        if self.state not in ("running", "holding"):
+            return
+        self._timer.stop()
+        self._set_state("aborted")
+        self.message.emit(f"Sequence ABORTED at step {self.index + 1}: {reason}")
+        self.abortRequested.emit(f"sequencer: {reason}")
+
+    def stop(self) -> None:
+        """Stop without safing (e.g. when a countdown hands over elsewhere)."""
+        self._timer.stop()
+        if self.state in ("running", "holding"):
+            self._set_state("idle")
+
+    def _set_state(self, state: str) -> None:
+        self.state = state
+        self.stateChanged.emit(state)
+
+    def _set_index(self, index: int) -> None:
+        self.index = index
+        self.stepChanged.emit(index)
+
+    def _enter_step(self, index: int) -> None:
+        # Skip steps whose "when" condition is false (or has no recent data)
+        while index < len(self.steps) and "when" in self.steps[index]:
+            cond = Condition.from_dict(self.steps[index]["when"])
+            result = cond.evaluate(self._lookup)
+            if result:
+                break
+            if result is None:
+                why = f"no recent data for {cond.sensor}"
+            else:
+                why = f"{cond} not met"
+            self.message.emit(f"Step {index + 1} skipped ({why})")
+            index += 1
+        self._step_started = time.monotonic()
+        self._held_at = None
+        self._waiting_for_data = False
+        if index >= len(self.steps):
+            self._timer.stop()
+            self._set_state("done")
+            self._set_index(-1)
+            self.message.emit(f"Sequence '{self.name}' complete")
+            return
+        self._set_index(index)
+        self._tick()
+
+    @Slot()
+    def _tick(self) -> None:
+        if self.state != "running" or not 0 <= self.index < len(self.steps):
+            return
+        step = self.steps[self.index]
+        n = self.index + 1
+        kind = step["type"]
+
+        if kind == "command":
+            if not self._can_command():
+                self.hold("commanding not allowed (key switch / authority)")
+                return
+            control = str(step["control"]).upper()
+            action = str(step["action"]).upper()
+            self.message.emit(f"Step {n}: {action} {control}")
+            if not self._send(control, action):
+                self.hold(f"{action} {control} was refused")
+                return
+            self._enter_step(self.index + 1)
+        elif kind == "wait":
+            if self.time_in_step() >= float(step["seconds"]):
+                self._enter_step(self.index + 1)
+        elif kind == "wait_until":
+            cond = Condition.from_dict(step)
+            result = cond.evaluate(self._lookup)
+            if result:
+                self.message.emit(f"Step {n}: {cond} met")
+                self._enter_step(self.index + 1)
+                return
+            # A stale reading never satisfies a wait; say so once per dropout
+            if (result is None) != self._waiting_for_data:
+                self._waiting_for_data = result is None
+                if result is None:
+                    self.message.emit(
+                        f"Step {n}: waiting, no recent data for {cond.sensor}"
+                    )
+            timeout = float(step.get("timeout_s", 0))
+            if timeout > 0 and self.time_in_step() > timeout:
+                on_timeout = step.get("on_timeout", "hold")
+                if on_timeout == "abort":
+                    self.abort(f"timed out waiting for {cond}")
+                elif on_timeout == "continue":
+                    self.message.emit(f"Step {n}: timed out on {cond}, continuing")
+                    self._enter_step(self.index + 1)
+                else:
+                    # Resuming restarts the timeout
+                    self.hold(f"timed out waiting for {cond}")
+                    self._step_started = time.monotonic()
+        elif kind == "hold":
+            self.hold(step.get("message", "hold point"))
+        elif kind == "log":
+            self.message.emit(f"Step {n}: {step.get('message', '')}")
+            self._enter_step(self.index + 1)
+
+    def time_in_step(self) -> float:
+        return time.monotonic() - self._step_started
+
+
+# -----------------------------
+# Ignition countdown
+# -----------------------------
+class Countdown(QObject):
+    """T-minus clock. Holds automatically if any go/no-go check fails during
+    terminal count; emits ignition at T-0 and then counts up (T+)."""
+
+    TICK_MS = 100
+
+    tick = Signal(float)  # seconds relative to T-0 (negative before ignition)
+    stateChanged = Signal(str)  # idle | counting | holding | fired
+    checksUpdated = Signal(object)  # list[tuple[str, bool]]
+    message = Signal(str)
+    ignition = Signal()
+
+    def __init__(
+        self,
+        checks: Callable[[], list[tuple[str, bool]]],
+        parent: Optional[QObject] = None,
+    ) -> None:
+        super().__init__(parent)
+        self._checks = checks
+        self.countdown_s = 60.0
+        self.terminal_count_s = 10.0
+        self.state = "idle"
+        self._remaining = self.countdown_s  # valid while idle/holding
+        self._t0 = 0.0  # monotonic time of T-0 while counting/fired
+        self._timer = QTimer(self)
+        self._timer.setInterval(self.TICK_MS)
+        self._timer.timeout.connect(self._tick)
+        self._timer.start()
+
+    def configure(self, countdown_s: float, terminal_count_s: float) -> None:
+        self.countdown_s = countdown_s
+        self.terminal_count_s = terminal_count_s
+        if self.state == "idle":
+            self._remaining = countdown_s
+
+    def relative_time(self) -> float:
+        if self.state in ("counting", "fired"):
+            return time.monotonic() - self._t0
+        return -self._remaining
+
+    def no_go(self) -> list[str]:
+        return [name for name, ok in self._checks() if not ok]
+
+    def start(self) -> None:
+        if self.state != "idle":
+            return
+        self.message.emit(f"Countdown started at T-{self._remaining:.0f} s")
+        self._count()
+
+    def hold(self, reason: str = "operator hold") -> None:
+        if self.state != "counting":
+            return
+        self._remaining = max(0.0, self._t0 - time.monotonic())
+        self._set_state("holding")
+        self.message.emit(f"HOLD at T-{self._remaining:.1f} s: {reason}")
+
+    def resume(self) -> None:
+        if self.state != "holding":
+            return
+        if self._remaining <= self.terminal_count_s:
+            failing = self.no_go()
+            if failing:
+                self.message.emit(f"Cannot resume, NO-GO: {', '.join(failing)}")
+                return
+        self.message.emit(f"Count resumed at T-{self._remaining:.1f} s")
+        self._count()
+
+    def recycle(self, reason: str = "operator recycle") -> None:
+        if self.state == "idle" and self._remaining == self.countdown_s:
+            return
+        self._remaining = self.countdown_s
+        self._set_state("idle")
+        self.message.emit(f"Countdown recycled to T-{self.countdown_s:.0f} s: {reason}")
+
+    def _count(self) -> None:
+        self._t0 = time.monotonic() + self._remaining
+        self._set_state("counting")
+        self._tick()
+
+    def _set_state(self, state: str) -> None:
+        self.state = state
+        self.stateChanged.emit(state)
+
+    @Slot()
+    def _tick(self) -> None:
+        checks = self._checks()
+        self.checksUpdated.emit(checks)
+        if self.state == "counting":
+            remaining = self._t0 - time.monotonic()
+            failing = [name for name, ok in checks if not ok]
+            if remaining <= self.terminal_count_s and failing:
+                self.hold(f"NO-GO: {', '.join(failing)}")
+            elif remaining <= 0:
+                self._set_state("fired")
+                self.message.emit("T-0: IGNITION")
+                self.ignition.emit()
+        self.tick.emit(self.relative_time())
 
 
 # -----------------------------
@@ -298,91 +2265,258 @@ class ControlsPanel(QGroupBox):
         self.setLayout(layout)
 
 
+# -----------------------------
+# Control metadata from the device config
+# -----------------------------
+# Per-control keys in /config (all optional):
+#   "group": sidebar box, "label": display name, "order": sort key,
+#   "danger": normal | caution | critical (test director only),
+#   "closeAll": include in Close All.
+# Without them the old naming rules apply: AV* -> VALVES (and Close All),
+# SAFE24/IGN -> Power, anything else -> Other.
+# Analog controls ("type": "analog") take "min", "max", "step" and "unit" and
+# are commanded with CONTROL <name> SET <value>; they are never in Close All.
+# A reported position within "tolerance" (default: one step) of the setpoint
+# confirms it.
+# Binary controls with "pulseMaxMs" also get a timed pulse, sent as
+# CONTROL <name> PULSE <ms> so the device enforces the timing; "pulseMs" is the
+# initial duration.
+DANGER_LEVELS = ("normal", "caution", "critical")
+DANGER_COLORS = {"caution": "#b58900", "critical": "#c62828"}
+LEGACY_GROUP_ORDER = {"VALVES": 100, "Power": 200, "Other": 300}
+
+
+@dataclass
+class ControlSpec:
+    name: str  # uppercased command name
+    group: str
+    label: str
+    order: int
+    danger: str = "normal"
+    close_all: bool = False
+    kind: str = "binary"  # binary (OPEN/CLOSE) | analog (SET <value>)
+    minimum: float = 0.0
+    maximum: float = 100.0
+    step: float = 1.0
+    unit: str = ""
+    tolerance: float = 1.0  # analog: |reported - setpoint| that still confirms
+    pulse_ms: int = 0  # initial pulse duration
+    pulse_max_ms: int = 0  # 0 -> no pulse action
+
+    @property
+    def decimals(self) -> int:
+        text = f"{self.step:g}"
+        return len(text.split(".", 1)[1]) if "." in text else 0
+
+    def format_value(self, value: float) -> str:
+        """Value rounded to the control's step, as sent and compared."""
+        return f"{round(value / self.step) * self.step:.{self.decimals}f}"
+
+    @classmethod
+    def from_config(cls, name: str, meta: Any) -> ControlSpec:
+        name = name.upper()
+        meta = meta if isinstance(meta, dict) else {}
+        if name.startswith("AV"):
+            legacy_group = "VALVES"
+        elif name in {"SAFE24", "IGN"}:
+            legacy_group = "Power"
+        else:
+            legacy_group = "Other"
+        group = str(meta.get("group") or legacy_group)
+        try:
+            order = int(meta.get("order", LEGACY_GROUP_ORDER.get(group, 300)))
+        except (TypeError, ValueError):
+            order = 300
+        # IGN was always treated as the critical control before metadata existed
+        legacy_danger = "critical" if name == "IGN" else "normal"
+        danger = str(meta.get("danger", legacy_danger)).lower()
+        if danger not in DANGER_LEVELS:
+            danger = "normal"
+        close_all = meta.get("closeAll", name.startswith("AV"))
+        spec = cls(
+            name=name,
+            group=group,
+            label=str(meta.get("label") or name),
+            order=order,
+            danger=danger,
+            close_all=bool(close_all),
+        )
+        if "pulseMaxMs" in meta:
+            spec.pulse_max_ms = int(meta["pulseMaxMs"])
+            spec.pulse_ms = int(meta.get("pulseMs", spec.pulse_max_ms))
+            if not 0 < spec.pulse_ms <= spec.pulse_max_ms:
+                raise ValueError(f"{name}: pulse needs 0 < pulseMs <= pulseMaxMs")
+        if str(meta.get("type", "binary")).lower() == "analog":
+            spec.kind = "analog"
+            spec.close_all = False
+            spec.pulse_max_ms = 0
+            spec.minimum = float(meta.get("min", spec.minimum))
+            spec.maximum = float(meta.get("max", spec.maximum))
+            spec.step = float(meta.get("step", spec.step))
+            spec.unit = str(meta.get("unit", ""))
+            spec.tolerance = float(meta.get("tolerance", spec.step))
+            if not spec.minimum < spec.maximum or spec.step <= 0:
+                raise ValueError(f"{name}: analog control needs min < max, step > 0")
+            if spec.tolerance < 0:
+                raise ValueError(f"{name}: tolerance must be >= 0")
+        return spec
+
+
 # -----------------------------
 # Controls sidebar with per-control Open/Close
 # -----------------------------
 class ControlsSidebar(QGroupBox):
     controlRequested = Signal(str, str)  # (name, action)
+    setpointRequested = Signal(str, float)  # (name, value) for analog controls
+    pulseRequested = Signal(str, int)  # (name, duration ms)
+    closeAllRequested = Signal(list)  # control names
 
     def __init__(
         self,
         parent: Optional[QWidget] = None,
-        controls: Optional[list[str]] = None,
+        controls: Optional[list[ControlSpec]] = None,
         general_widget: Optional[QWidget] = None,
     ) -> None:
         super().__init__("Controls", parent)
         if controls is None:
             controls = []
-
-        av_controls = [c for c in controls if c.startswith("AV")]
-        power_controls = [c for c in controls if c in {"SAFE24", "IGN"}]
-        other_controls = [
-            c for c in controls if not c.startswith("AV") and c not in {"SAFE24", "IGN"}
-        ]
+        # Stable sort keeps config order among controls with the same order
+        controls = sorted(controls, key=lambda c: c.order)
+        self.specs: dict[str, ControlSpec] = {c.name: c for c in controls}
 
         self.controlButtons: dict[str, QPushButton] = {}
+        self.controlIndicators: dict[str, QLabel] = {}  # name -> state indicator
+        self.controlStatus: dict[str, QLabel] = {}  # name -> ack status line
+
+        self.setpointEdits: dict[str, QDoubleSpinBox] = {}  # analog name -> value
+        self.pulseButtons: dict[str, QPushButton] = {}
+        self.controlBoxes: dict[str, QGroupBox] = {}
 
         v = QVBoxLayout(self)
 
-        def _make_control_box(name: str, parent_widget: QWidget) -> QGroupBox:
-            box = QGroupBox(name, parent_widget)
-            h = QHBoxLayout(box)
-            btn_open = QPushButton("Open", box)
-            btn_close = QPushButton("Close", box)
-            self.controlButtons[f"{name}_open"] = btn_open
-            self.controlButtons[f"{name}_close"] = btn_close
-            btn_open.clicked.connect(
-                lambda _=False, n=name: self.controlRequested.emit(n, "OPEN")
+        def _add_analog_widgets(
+            spec: ControlSpec, box: QGroupBox, box_v: QVBoxLayout
+        ) -> None:
+            # Slider and spin box stay in sync; nothing is sent until "Set"
+            slider = QSlider(Qt.Orientation.Horizontal, box)
+            slider.setRange(0, round((spec.maximum - spec.minimum) / spec.step))
+            spin = QDoubleSpinBox(box)
+            spin.setRange(spec.minimum, spec.maximum)
+            spin.setSingleStep(spec.step)
+            spin.setDecimals(spec.decimals)
+            if spec.unit:
+                spin.setSuffix(f" {spec.unit}")
+            slider.valueChanged.connect(
+                lambda i: spin.setValue(spec.minimum + i * spec.step)
+            )
+            spin.valueChanged.connect(
+                lambda x: slider.setValue(round((x - spec.minimum) / spec.step))
+            )
+            btn_set = QPushButton("Set", box)
+            btn_set.clicked.connect(
+                lambda _=False: self._request_setpoint(spec.name, spin.value())
             )
-            btn_close.clicked.connect(
-                lambda _=False, n=name: self.controlRequested.emit(n, "CLOSE")
+            self.setpointEdits[spec.name] = spin
+            h = QHBoxLayout()
+            h.addWidget(spin, 1)
+            h.addWidget(btn_set)
+            box_v.addWidget(slider)
+            box_v.addLayout(h)
+
+        def _add_pulse_widgets(
+            spec: ControlSpec, box: QGroupBox, box_v: QVBoxLayout
+        ) -> None:
+            duration = QSpinBox(box)
+            duration.setRange(1, spec.pulse_max_ms)
+            duration.setValue(spec.pulse_ms)
+            duration.setSuffix(" ms")
+            btn_pulse = QPushButton("Pulse", box)
+            btn_pulse.setToolTip(f"Open for the set time (max {spec.pulse_max_ms} ms)")
+            btn_pulse.clicked.connect(
+                lambda _=False: self._request_pulse(spec.name, duration.value())
             )
-            h.addWidget(btn_open)
-            h.addWidget(btn_close)
-            box.setLayout(h)
+            self.pulseButtons[spec.name] = btn_pulse
+            h = QHBoxLayout()
+            h.addWidget(duration, 1)
+            h.addWidget(btn_pulse)
+            box_v.addLayout(h)
+
+        def _make_control_box(spec: ControlSpec, parent_widget: QWidget) -> QGroupBox:
+            name = spec.name
+            box = QGroupBox(spec.label, parent_widget)
+            box.setToolTip(f"{name} ({spec.danger})" if spec.label != name else name)
+            if spec.danger in DANGER_COLORS:
+                color = DANGER_COLORS[spec.danger]
+                box.setStyleSheet(f"QGroupBox::title {{ color: {color}; }}")
+            box_v = QVBoxLayout(box)
+            indicator = QLabel("UNKNOWN", box)
+            indicator.setAlignment(Qt.AlignmentFlag.AlignCenter)
+            self.controlIndicators[name] = indicator
+            box_v.addWidget(indicator)
+            self.set_indicator(name, "UNKNOWN", "#757575")
+            if spec.kind == "analog":
+                _add_analog_widgets(spec, box, box_v)
+            else:
+                h = QHBoxLayout()
+                btn_open = QPushButton("Open", box)
+                btn_close = QPushButton("Close", box)
+                self.controlButtons[f"{name}_open"] = btn_open
+                self.controlButtons[f"{name}_close"] = btn_close
+                btn_open.clicked.connect(
+                    lambda _=False, n=name: self._request(n, "OPEN")
+                )
+                btn_close.clicked.connect(
+                    lambda _=False, n=name: self._request(n, "CLOSE")
+                )
+                h.addWidget(btn_open)
+                h.addWidget(btn_close)
+                box_v.addLayout(h)
+                if spec.pulse_max_ms:
+                    _add_pulse_widgets(spec, box, box_v)
+            status = QLabel("", box)
+            status.setAlignment(Qt.AlignmentFlag.AlignCenter)
+            status.setVisible(False)
+            self.controlStatus[name] = status
+            box_v.addWidget(status)
+            box.setLayout(box_v)
+            self.controlBoxes[name] = box
             return box
 
-        # Valves subbox
-        valves_box = QGroupBox("VALVES", self)
-        valves_layout = QVBoxLayout(valves_box)
-        for name in av_controls:
-            valves_layout.addWidget(_make_control_box(name, valves_box))
-
-        # Add "Close All" and "Default Positions" buttons
-        self.btn_close_all = QPushButton("Close All", valves_box)
-        self.btn_default_positions = QPushButton("Default Positions", valves_box)
-        valves_layout.addWidget(self.btn_close_all)
-        valves_layout.addWidget(self.btn_default_positions)
+        # One box per group, ordered by the lowest order among its controls
+        groups: dict[str, list[ControlSpec]] = {}
+        for spec in controls:
+            groups.setdefault(spec.group, []).append(spec)
+        # Boxes holding commanding widgets; disabled as a whole when not armed
+        self._armed_boxes: list[QGroupBox] = []
+        for group, specs in sorted(groups.items(), key=lambda g: g[1][0].order):
+            group_box = QGroupBox(group, self)
+            group_layout = QVBoxLayout(group_box)
+            for spec in specs:
+                group_layout.addWidget(_make_control_box(spec, group_box))
+            group_layout.addStretch(1)
+            group_box.setLayout(group_layout)
+            v.addWidget(group_box)
+            self._armed_boxes.append(group_box)
+
+        # "Close All" and "Default Positions" act across every group
+        all_box = QGroupBox("All Controls", self)
+        all_layout = QVBoxLayout(all_box)
+        self.btn_close_all = QPushButton("Close All", all_box)
+        self.btn_default_positions = QPushButton("Default Positions", all_box)
+        all_layout.addWidget(self.btn_close_all)
+        all_layout.addWidget(self.btn_default_positions)
+        all_box.setLayout(all_layout)
+        v.addWidget(all_box)
+        self._armed_boxes.append(all_box)
 
         # Connect buttons to signals
+        self.close_all_controls = [c.name for c in controls if c.close_all]
+        self.btn_close_all.setToolTip(", ".join(self.close_all_controls))
         self.btn_close_all.clicked.connect(
-            lambda: [self.controlRequested.emit(name, "CLOSE") for name in av_controls]
+            lambda: self.closeAllRequested.emit(list(self.close_all_controls))
         )
         self.btn_default_positions.clicked.connect(self.handleDefaultButton)
 
-        valves_layout.addStretch(1)
-        valves_box.setLayout(valves_layout)
-        v.addWidget(valves_box)
-
-        # Power subbox
-        power_box = QGroupBox("Power", self)
-        power_layout = QVBoxLayout(power_box)
-        for name in power_controls:
-            power_layout.addWidget(_make_control_box(name, power_box))
-        power_layout.addStretch(1)
-        power_box.setLayout(power_layout)
-        v.addWidget(power_box)
-
-        # Other controls subbox (controls from config that don't fit AV or Power)
-        if other_controls:
-            other_box = QGroupBox("Other", self)
-            other_layout = QVBoxLayout(other_box)
-            for name in other_controls:
-                other_layout.addWidget(_make_control_box(name, other_box))
-            other_layout.addStretch(1)
-            other_box.setLayout(other_layout)
-            v.addWidget(other_box)
-
         # Spacer so the General group stays at the bottom
         v.addStretch(1)
 
@@ -394,6 +2528,84 @@ class ControlsSidebar(QGroupBox):
         general_box.setLayout(general_layout)
         v.addWidget(general_box)
 
+    def _request(self, name: str, action: str) -> None:
+        # Critical OPENs are confirmed by MainWindow.request_control
+        self.controlRequested.emit(name, action)
+
+    def _request_setpoint(self, name: str, value: float) -> None:
+        spec = self.specs[name]
+        if spec.danger == "critical":
+            confirm = QMessageBox.question(
+                self,
+                "Confirm",
+                f"{spec.label} is marked critical. Set {name} to "
+                f"{spec.format_value(value)} {spec.unit}?",
+                QMessageBox.StandardButton.Yes | QMessageBox.StandardButton.No,
+                QMessageBox.StandardButton.No,
+            )
+            if confirm != QMessageBox.StandardButton.Yes:
+                return
+        self.setpointRequested.emit(name, value)
+
+    def _request_pulse(self, name: str, duration_ms: int) -> None:
+        spec = self.specs[name]
+        if spec.danger == "critical":
+            confirm = QMessageBox.question(
+                self,
+                "Confirm",
+                f"{spec.label} is marked critical. Pulse {name} for {duration_ms} ms?",
+                QMessageBox.StandardButton.Yes | QMessageBox.StandardButton.No,
+                QMessageBox.StandardButton.No,
+            )
+            if confirm != QMessageBox.StandardButton.Yes:
+                return
+        self.pulseRequested.emit(name, duration_ms)
+
+    def set_locked_controls(self, names: set[str]) -> None:
+        """Disable individual control boxes (e.g. critical ones for this role)."""
+        for name, box in self.controlBoxes.items():
+            box.setEnabled(name not in names)
+
+    def set_pulse_active(self, name: str, active: bool) -> None:
+        btn = self.pulseButtons.get(name)
+        if btn is None:
+            return
+        btn.setText("Pulsing…" if active else "Pulse")
+        btn.setEnabled(not active)
+        btn.setStyleSheet("background-color: #b58900; color: white;" if active else "")
+
+    def set_armed(self, armed: bool) -> None:
+        """Enable or disable every command widget. Disabling the parent boxes
+        keeps each button's own open/closed enablement for when it is re-armed."""
+        for box in self._armed_boxes:
+            box.setEnabled(armed)
+
+    def set_indicator(
+        self, name: str, text: str, color: str, stale: bool = False
+    ) -> None:
+        """Show a control's state; stale ones are dimmed so they don't read as live."""
+        label = self.controlIndicators.get(name)
+        if label is None:
+            return
+        if stale:
+            text += " (stale)"
+            color = QColor(color).lighter(160).name()
+        label.setText(text)
+        label.setStyleSheet(
+            f"background-color: {color}; color: white; font-weight: bold; "
+            f"border-radius: 3px; padding: 2px;"
+            + (" font-style: italic;" if stale else "")
+        )
+
+    def set_ack_status(self, name: str, text: str, color: str = "") -> None:
+        """Show a command acknowledgement note under a control; empty text hides it."""
+        label = self.controlStatus.get(name)
+        if label is None:
+            return
+        label.setText(text)
+        label.setStyleSheet(f"color: {color}; font-weight: bold;" if color else "")
+        label.setVisible(bool(text))
+
     def handleDefaultButton(self):
         # Open a dialog to confirm whether the user wants to reset to default. Warn that this will dump any oxidizer to
         # the air
@@ -411,36 +2623,448 @@ class ControlsSidebar(QGroupBox):
 
 
 # -----------------------------
-# Credentials dialog (username + password)
+# Credentials dialog (username + password)
+# -----------------------------
+class CredentialsDialog(QDialog):
+    def __init__(
+        self,
+        creds: dict[str, str],
+        profile: str,
+        storage: str,
+        parent: Optional[QWidget] = None,
+    ) -> None:
+        super().__init__(parent)
+        self.setWindowTitle(f"Credentials [{profile}]")
+        form = QFormLayout(self)
+        self.user_edit = QLineEdit(creds.get("api_username", ""))
+        self.pass_edit = QLineEdit(creds.get("api_password", ""))
+        self.pass_edit.setEchoMode(QLineEdit.EchoMode.Password)
+        self.redis_user_edit = QLineEdit(creds.get("redis_username", ""))
+        self.redis_pass_edit = QLineEdit(creds.get("redis_password", ""))
+        self.redis_pass_edit.setEchoMode(QLineEdit.EchoMode.Password)
+        form.addRow("API username", self.user_edit)
+        form.addRow("API password", self.pass_edit)
+        form.addRow("Redis username", self.redis_user_edit)
+        form.addRow("Redis password", self.redis_pass_edit)
+        storage_lbl = QLabel(f"Stored in {storage}")
+        storage_lbl.setWordWrap(True)
+        form.addRow(storage_lbl)
+        buttons = QDialogButtonBox(
+            QDialogButtonBox.StandardButton.Ok | QDialogButtonBox.StandardButton.Cancel,
+            parent=self,
+        )
+        buttons.accepted.connect(self.accept)
+        buttons.rejected.connect(self.reject)
+        form.addRow(buttons)
+
+    def values(self) -> dict[str, str]:
+        return {
+            "api_username": self.user_edit.text().strip(),
+            "api_password": self.pass_edit.text().strip(),
+            "redis_username": self.redis_user_edit.text().strip(),
+            "redis_password": self.redis_pass_edit.text().strip(),
+        }
+
+
+# -----------------------------
+# Sequencer panel: procedure timeline with hold/resume/abort
+# -----------------------------
+class SequencerPanel(QGroupBox):
+    def __init__(self, sequencer: Sequencer, parent: Optional[QWidget] = None):
+        super().__init__("Sequencer", parent)
+        self.sequencer = sequencer
+        v = QVBoxLayout(self)
+
+        top = QHBoxLayout()
+        self.btn_load = QPushButton("Load Procedure…")
+        self.name_label = QLabel("No procedure loaded")
+        self.state_label = QLabel("idle")
+        sfont = self.state_label.font()
+        sfont.setBold(True)
+        self.state_label.setFont(sfont)
+        top.addWidget(self.btn_load)
+        top.addWidget(self.name_label, 1)
+        top.addWidget(self.state_label)
+        v.addLayout(top)
+
+        self.steps_list = QListWidget()
+        self.steps_list.setSelectionMode(QListWidget.SelectionMode.NoSelection)
+        self.steps_list.setMaximumHeight(160)
+        v.addWidget(self.steps_list)
+
+        buttons = QHBoxLayout()
+        self.btn_start = QPushButton("Start")
+        self.btn_hold = QPushButton("Hold")
+        self.btn_resume = QPushButton("Resume")
+        self.btn_abort = QPushButton("Abort")
+        for b in (self.btn_start, self.btn_hold, self.btn_resume, self.btn_abort):
+            buttons.addWidget(b)
+        v.addLayout(buttons)
+
+        self.btn_load.clicked.connect(self.load_procedure)
+        self.btn_start.clicked.connect(sequencer.start)
+        self.btn_hold.clicked.connect(lambda: sequencer.hold("operator hold"))
+        self.btn_resume.clicked.connect(sequencer.resume)
+        self.btn_abort.clicked.connect(lambda: sequencer.abort("operator abort"))
+        sequencer.loaded.connect(self._on_loaded)
+        sequencer.stepChanged.connect(self._on_step_changed)
+        sequencer.stateChanged.connect(self._on_state_changed)
+        self._on_state_changed(sequencer.state)
+
+    def load_procedure(self) -> None:
+        path, _ = QFileDialog.getOpenFileName(
+            self, "Load Procedure", "", "Procedures (*.json *.yaml *.yml)"
+        )
+        if not path:
+            return
+        try:
+            name, steps = load_procedure(Path(path))
+            self.sequencer.load(name, steps)
+        except (OSError, ValueError, RuntimeError) as e:
+            QMessageBox.warning(self, "Procedure", f"Cannot load {path}:\n{e}")
+            return
+        self.sequencer.message.emit(f"Loaded procedure '{name}' ({len(steps)} steps)")
+
+    @Slot(str)
+    def _on_loaded(self, name: str) -> None:
+        """List steps with their planned start time; after a wait_until or hold
+        the times are lower bounds."""
+        self.name_label.setText(name)
+        self.steps_list.clear()
+        offset = 0.0
+        exact = True
+        for step in self.sequencer.steps:
+            prefix = f"T+{offset:6.1f}" if exact else f"T+≥{offset:5.1f}"
+            self.steps_list.addItem(QListWidgetItem(f"{prefix}  {describe_step(step)}"))
+            if step["type"] == "wait":
+                offset += float(step["seconds"])
+            elif step["type"] in ("wait_until", "hold"):
+                exact = False
+
+    @Slot(int)
+    def _on_step_changed(self, index: int) -> None:
+        for i in range(self.steps_list.count()):
+            item = self.steps_list.item(i)
+            font = item.font()
+            font.setBold(i == index)
+            item.setFont(font)
+            if i == index:
+                item.setBackground(Qt.GlobalColor.yellow)
+            else:
+                item.setBackground(Qt.GlobalColor.transparent)
+            done = index < 0 and self.sequencer.state == "done"
+            if i < index or done:
+                item.setForeground(Qt.GlobalColor.gray)
+            else:
+                item.setForeground(self.palette().text())
+        if index >= 0:
+            self.steps_list.scrollToItem(self.steps_list.item(index))
+
+    @Slot(str)
+    def _on_state_changed(self, state: str) -> None:
+        colors = {"running": "green", "holding": "#b58900", "aborted": "red"}
+        self.state_label.setText(state.upper())
+        self.state_label.setStyleSheet(f"color: {colors.get(state, 'gray')};")
+        active = state in ("running", "holding")
+        self.btn_load.setEnabled(not active)
+        self.btn_start.setEnabled(not active and bool(self.sequencer.steps))
+        self.btn_hold.setEnabled(state == "running")
+        self.btn_resume.setEnabled(state == "holding")
+        self.btn_abort.setEnabled(active)
+
+
+# -----------------------------
+# Countdown panel: T-minus clock and go/no-go poll
 # -----------------------------
-class CredentialsDialog(QDialog):
+class CountdownPanel(QGroupBox):
+    def __init__(
+        self, countdown: Countdown, polls: list[str], parent: Optional[QWidget] = None
+    ):
+        super().__init__("Countdown", parent)
+        self.countdown = countdown
+        h = QHBoxLayout(self)
+
+        clock_v = QVBoxLayout()
+        self.clock_label = QLabel("")
+        cfont = QFontDatabase.systemFont(QFontDatabase.SystemFont.FixedFont)
+        cfont.setPointSize(28)
+        cfont.setBold(True)
+        self.clock_label.setFont(cfont)
+        self.state_label = QLabel("")
+        clock_v.addWidget(self.clock_label)
+        clock_v.addWidget(self.state_label)
+        h.addLayout(clock_v)
+
+        buttons = QVBoxLayout()
+        self.btn_start = QPushButton("Start Count")
+        self.btn_hold = QPushButton("Hold")
+        self.btn_resume = QPushButton("Resume")
+        self.btn_recycle = QPushButton("Recycle")
+        for b in (self.btn_start, self.btn_hold, self.btn_resume, self.btn_recycle):
+            buttons.addWidget(b)
+        h.addLayout(buttons)
+
+        # Manual polls (operator ticks each one) + automatic checks
+        polls_v = QVBoxLayout()
+        self.poll_boxes: dict[str, QCheckBox] = {}
+        for poll in polls:
+            box = QCheckBox(poll)
+            polls_v.addWidget(box)
+            self.poll_boxes[poll] = box
+        polls_v.addStretch(1)
+        h.addLayout(polls_v)
+        self.checks_list = QListWidget()
+        self.checks_list.setSelectionMode(QListWidget.SelectionMode.NoSelection)
+        self.checks_list.setMaximumHeight(140)
+        h.addWidget(self.checks_list, 1)
+
+        self.btn_start.clicked.connect(countdown.start)
+        self.btn_hold.clicked.connect(lambda: countdown.hold("operator hold"))
+        self.btn_resume.clicked.connect(countdown.resume)
+        self.btn_recycle.clicked.connect(lambda: countdown.recycle("operator recycle"))
+        countdown.tick.connect(self._on_tick)
+        countdown.stateChanged.connect(self._on_state_changed)
+        countdown.checksUpdated.connect(self._on_checks)
+        self._on_state_changed(countdown.state)
+        self._on_tick(countdown.relative_time())
+
+    def manual_checks(self) -> list[tuple[str, bool]]:
+        return [(name, box.isChecked()) for name, box in self.poll_boxes.items()]
+
+    @Slot(float)
+    def _on_tick(self, t: float) -> None:
+        sign = "T+" if t >= 0 else "T-"
+        mins, secs = divmod(abs(t), 60)
+        self.clock_label.setText(f"{sign}{int(mins):02d}:{secs:04.1f}")
+
+    @Slot(str)
+    def _on_state_changed(self, state: str) -> None:
+        colors = {"counting": "green", "holding": "#b58900", "fired": "red"}
+        self.state_label.setText(state.upper())
+        self.state_label.setStyleSheet(f"color: {colors.get(state, 'gray')};")
+        self.btn_start.setEnabled(state == "idle")
+        self.btn_hold.setEnabled(state == "counting")
+        self.btn_resume.setEnabled(state == "holding")
+        self.btn_recycle.setEnabled(state != "counting")
+        if state == "idle":
+            for box in self.poll_boxes.values():
+                box.setChecked(False)
+
+    @Slot(object)
+    def _on_checks(self, checks: list[tuple[str, bool]]) -> None:
+        auto = [c for c in checks if c[0] not in self.poll_boxes]
+        if self.checks_list.count() != len(auto):
+            self.checks_list.clear()
+            for _ in auto:
+                self.checks_list.addItem(QListWidgetItem(""))
+        for i, (name, ok) in enumerate(auto):
+            item = self.checks_list.item(i)
+            item.setText(f"{'GO' if ok else 'NO-GO':5}  {name}")
+            item.setForeground(QColor("green" if ok else "red"))
+
+
+# -----------------------------
+# P&ID view: SVG piping diagram with clickable valves and live readouts
+# -----------------------------
+class PidValveItem(QGraphicsRectItem):
+    """Clickable, state-colored overlay on a valve's SVG element."""
+
     def __init__(
-        self, username: str = "", password: str = "", parent: Optional[QWidget] = None
+        self, control: str, rect: QRectF, on_click: Callable[[str, Any], None]
     ) -> None:
+        super().__init__(rect)
+        self.control = control
+        self._on_click = on_click
+        self.setPen(QPen(Qt.PenStyle.NoPen))
+        self.setCursor(Qt.CursorShape.PointingHandCursor)
+        self.setToolTip(f"{control}: UNKNOWN")
+
+    def mousePressEvent(self, event) -> None:
+        if event.button() == Qt.MouseButton.LeftButton:
+            self._on_click(self.control, event.screenPos())
+            event.accept()
+        else:
+            super().mousePressEvent(event)
+
+
+class PidPanel(QWidget):
+    controlRequested = Signal(str, str)  # (name, action)
+
+    def __init__(self, parent: Optional[QWidget] = None) -> None:
         super().__init__(parent)
-        self.setWindowTitle("API Credentials")
-        form = QFormLayout(self)
-        self.user_edit = QLineEdit(username)
-        self.pass_edit = QLineEdit(password)
-        self.pass_edit.setEchoMode(QLineEdit.EchoMode.Password)
-        form.addRow("Username", self.user_edit)
-        form.addRow("Password", self.pass_edit)
-        buttons = QDialogButtonBox(
-            QDialogButtonBox.StandardButton.Ok | QDialogButtonBox.StandardButton.Cancel,
-            parent=self,
-        )
-        buttons.accepted.connect(self.accept)
-        buttons.rejected.connect(self.reject)
-        form.addRow(buttons)
+        v = QVBoxLayout(self)
+        v.setContentsMargins(0, 0, 0, 0)
+        self.placeholder = QLabel("No P&ID loaded (set pid_svg in settings)")
+        self.placeholder.setAlignment(Qt.AlignmentFlag.AlignCenter)
+        self.scene = QGraphicsScene(self)
+        self.view = QGraphicsView(self.scene)
+        self.view.setRenderHint(QPainter.RenderHint.Antialiasing)
+        self.view.setVisible(False)
+        v.addWidget(self.placeholder)
+        v.addWidget(self.view)
+        self._renderer: Optional[QSvgRenderer] = None  # must outlive the SVG item
+        self._valves: dict[str, PidValveItem] = {}
+        self._sensors: dict[str, QGraphicsSimpleTextItem] = {}
+        self._armed = False
+        self._locked: set[str] = set()  # controls this role may not command
+        self._analog: set[str] = set()  # set from the sidebar, not OPEN/CLOSE
+
+    def load(
+        self, path: Path, valves: dict[str, str], sensors: dict[str, str]
+    ) -> list[str]:
+        """Render the diagram and attach overlays. Returns the mapped element
+        IDs missing from the SVG; raises ValueError if it cannot be rendered."""
+        renderer = QSvgRenderer(str(path))
+        if not renderer.isValid():
+            raise ValueError(f"cannot render {path}")
+        self.scene.clear()
+        self._valves.clear()
+        self._sensors.clear()
+        self._renderer = renderer
+        diagram = QGraphicsSvgItem()
+        diagram.setSharedRenderer(renderer)
+        self.scene.addItem(diagram)
+
+        missing: list[str] = []
+
+        def _bounds(element: str) -> Optional[QRectF]:
+            if not renderer.elementExists(element):
+                missing.append(element)
+                return None
+            rect = renderer.boundsOnElement(element)
+            return renderer.transformForElement(element).mapRect(rect)
+
+        for control, element in valves.items():
+            rect = _bounds(element)
+            if rect is None:
+                continue
+            item = PidValveItem(control.upper(), rect, self._on_valve_clicked)
+            self.scene.addItem(item)
+            self._valves[control.upper()] = item
+            self.set_valve_state(control.upper(), "UNKNOWN", "#757575")
+        for sensor, element in sensors.items():
+            rect = _bounds(element)
+            if rect is None:
+                continue
+            text = QGraphicsSimpleTextItem("--")
+            font = text.font()
+            font.setBold(True)
+            text.setFont(font)
+            text.setPos(rect.right() + 4, rect.top())
+            self.scene.addItem(text)
+            self._sensors[sensor] = text
+
+        self.placeholder.setVisible(False)
+        self.view.setVisible(True)
+        self._fit()
+        return missing
+
+    def set_armed(self, armed: bool) -> None:
+        self._armed = armed
+
+    def set_locked_controls(self, names: set[str]) -> None:
+        self._locked = set(names)
+
+    def set_analog_controls(self, names: set[str]) -> None:
+        self._analog = set(names)
+
+    def set_valve_state(
+        self, control: str, text: str, color: str, stale: bool = False
+    ) -> None:
+        item = self._valves.get(control)
+        if item is None:
+            return
+        fill = QColor(color)
+        fill.setAlpha(70 if stale else 140)
+        item.setBrush(fill)
+        item.setToolTip(f"{control}: {text}" + (" (stale)" if stale else ""))
+
+    @Slot(str, str)
+    def set_readout(self, series: str, text: str) -> None:
+        """Update the overlay for a series ("DEVICE:NAME") or its bare name."""
+        for key in (series, series.split(":", 1)[-1]):
+            item = self._sensors.get(key)
+            if item is not None:
+                item.setText(text)
+
+    def _on_valve_clicked(self, control: str, pos) -> None:
+        allowed = self._armed and control not in self._locked
+        menu = QMenu(self)
+        title = menu.addAction(control if allowed else f"{control} (locked)")
+        title.setEnabled(False)
+        menu.addSeparator()
+        if control in self._analog:
+            menu.addAction("Set position in the controls sidebar").setEnabled(False)
+            menu.exec(pos)
+            return
+        for action in ("OPEN", "CLOSE"):
+            act = menu.addAction(action.title())
+            act.setEnabled(allowed)
+            act.triggered.connect(
+                lambda _=False, a=action: self.controlRequested.emit(control, a)
+            )
+        menu.exec(pos)
+
+    def _fit(self) -> None:
+        if self._renderer is not None:
+            self.view.fitInView(
+                self.scene.itemsBoundingRect(), Qt.AspectRatioMode.KeepAspectRatio
+            )
 
-    def values(self) -> tuple[str, str]:
-        return self.user_edit.text().strip(), self.pass_edit.text().strip()
+    def resizeEvent(self, event) -> None:
+        super().resizeEvent(event)
+        self._fit()
+
+
+# -----------------------------
+# Alarm banner (shown above the graphs until acknowledged)
+# -----------------------------
+class AlarmBanner(QWidget):
+    acknowledged = Signal()
+
+    def __init__(self, parent: Optional[QWidget] = None) -> None:
+        super().__init__(parent)
+        self.setAutoFillBackground(True)
+        self.setStyleSheet("background-color: #c0392b; color: white;")
+        h = QHBoxLayout(self)
+        h.setContentsMargins(8, 4, 8, 4)
+        self.label = QLabel("")
+        lfont = self.label.font()
+        lfont.setPointSize(13)
+        lfont.setBold(True)
+        self.label.setFont(lfont)
+        self.label.setWordWrap(True)
+        self.btn_ack = QPushButton("Acknowledge")
+        self.btn_ack.clicked.connect(self.acknowledge)
+        h.addWidget(self.label, 1)
+        h.addWidget(self.btn_ack)
+        self._active: list[str] = []
+        self.setVisible(False)
+
+    def raise_alarm(self, text: str) -> None:
+        stamp = datetime.now().strftime("%H:%M:%S")
+        self._active.append(f"{stamp}  {text}")
+        more = f"  (+{len(self._active) - 1} more)" if len(self._active) > 1 else ""
+        self.label.setText(self._active[-1] + more)
+        self.label.setToolTip("\n".join(self._active))
+        self.setVisible(True)
+        QApplication.beep()
+
+    def has_alarms(self) -> bool:
+        return bool(self._active)
+
+    def acknowledge(self) -> None:
+        self._active.clear()
+        self.setVisible(False)
+        self.acknowledged.emit()
 
 
 # -----------------------------
 # Dynamic graph panel using pyqtgraph
 # -----------------------------
 class GraphPanel(QWidget):
+    readoutChanged = Signal(str, str)  # (series, display text)
+
     def __init__(
         self, columns: int = 1, max_points: int = 2000, parent: Optional[QWidget] = None
     ):
@@ -512,7 +3136,8 @@ class GraphPanel(QWidget):
         # Plot widget to the right of the readout
         plot = pg.PlotWidget()
         plot.showGrid(x=True, y=True)
-        curve = plot.plot()
+        # NaN samples break the line so data gaps stay visible
+        curve = plot.plot(connect="finite")
 
         # Place left_widget and plot in the same row
         self.grid.addWidget(left_widget, row, 0, alignment=Qt.AlignmentFlag.AlignTop)
@@ -557,12 +3182,7 @@ class GraphPanel(QWidget):
         ys.append(y)
 
         # Update readout but skip expensive rendering
-        if name in self._readouts:
-            unit = self._units.get(name) or self._units.get(name.split(":", 1)[-1])
-            disp_y = y - self._tare_offsets.get(name, 0.0)
-            self._readouts[name].setText(
-                f"{disp_y:.3f} {unit}" if unit else f"{disp_y:.3f}"
-            )
+        self._set_readout(name, y)
 
     def add_point_batch(self, name: str, points: list[tuple[float, float]]) -> None:
         """Add multiple points for a series and render once."""
@@ -630,21 +3250,42 @@ class GraphPanel(QWidget):
         curve.setData(plot_xs, plot_ys)
 
         # Update live readout with last point
-        if name in self._readouts and ys:
-            unit = self._units.get(name) or self._units.get(name.split(":", 1)[-1])
-            disp_y = ys[-1] - self._tare_offsets.get(name, 0.0)
-            self._readouts[name].setText(
-                f"{disp_y:.3f} {unit}" if unit else f"{disp_y:.3f}"
-            )
+        if ys:
+            self._set_readout(name, ys[-1])
+
+    def _set_readout(self, name: str, y: float) -> None:
+        """Show a raw sample (tare applied) in the series readout and publish it."""
+        if name not in self._readouts:
+            return
+        unit = self._units.get(name) or self._units.get(name.split(":", 1)[-1])
+        disp_y = y - self._tare_offsets.get(name, 0.0)
+        text = f"{disp_y:.3f} {unit}" if unit else f"{disp_y:.3f}"
+        self._readouts[name].setText(text)
+        self.readoutChanged.emit(name, text)
+
+    def mark_gap(self) -> None:
+        """Insert a NaN after the last sample of every series so the plots show
+        a break where data was missed."""
+        for name, (xs, ys) in self._data.items():
+            if not xs or not isfinite(ys[-1]):
+                continue
+            t = xs[-1] + 1e-6
+            xs.append(t)
+            ys.append(float("nan"))
+            self._last_t[name] = t
+
+    def _last_value(self, name: str) -> Optional[float]:
+        """Most recent finite sample of a series (skips gap markers)."""
+        _xs, ys = self._data.get(name, ([], []))
+        return next((y for y in reversed(ys) if isfinite(y)), None)
 
     def tare_series(self, name: str) -> None:
         """Set a display-only zero offset for a series to its current value and refresh the plot."""
         if name not in self._data:
             return
-        xs, ys = self._data[name]
-        if not ys:
+        current = self._last_value(name)
+        if current is None:
             return  # nothing to tare yet
-        current = ys[-1]
         self._tare_offsets[name] = float(current)
         # Refresh existing curve with new offset and update readout
         self._refresh_series(name)
@@ -660,6 +3301,7 @@ class GraphPanel(QWidget):
                 curve.setData([], [])
             if name in self._readouts:
                 self._readouts[name].setText("--")
+                self.readoutChanged.emit(name, "--")
             return
 
         t = xs[-1]
@@ -690,24 +3332,27 @@ class GraphPanel(QWidget):
         curve = self._curves.get(name)
         if curve is not None:
             curve.setData(plot_xs, plot_ys)
-        if name in self._readouts and ys:
-            unit = self._units.get(name) or self._units.get(name.split(":", 1)[-1])
-            disp_y = ys[-1] - self._tare_offsets.get(name, 0.0)
-            self._readouts[name].setText(
-                f"{disp_y:.3f} {unit}" if unit else f"{disp_y:.3f}"
-            )
+        last = self._last_value(name)
+        if last is not None:
+            self._set_readout(name, last)
 
 
 class DataLogger(QObject):
     """Buffered wide-CSV logger with per-device row assembly and periodic flushes.
 
     CSV layout: device,t,<sensor1>,<sensor2>,...
+    Ignition is a row with device "T0" and t = device clock at T-0.
+    Events (data gaps etc.) go to a sidecar <name>-events.csv:
+    wall_time,event,detail
     """
 
     def __init__(self, parent: Optional[QObject] = None) -> None:
         super().__init__(parent)
         self._file = None
         self._writer = None
+        self._events_file = None
+        self._events_writer = None
+        self.events_path = None
         self._buffer = []  # list of ready-to-write rows
         self.path = None
         self._columns = []  # sensor names
@@ -715,6 +3360,7 @@ class DataLogger(QObject):
         # Current in-progress rows per device
         self._current_t = {}
         self._current_row = {}
+        self._pending_markers = []  # marker rows waiting for the header
 
     @property
     def is_active(self) -> bool:
@@ -740,6 +3386,59 @@ class DataLogger(QObject):
         # Reset per-device accumulators
         self._current_t.clear()
         self._current_row.clear()
+        self._pending_markers.clear()
+
+        self.events_path = self.path.with_name(f"{self.path.stem}-events.csv")
+        self._events_file = open(self.events_path, "w", newline="", encoding="utf-8")
+        self._events_writer = csv.writer(self._events_file)
+        self._events_writer.writerow(["wall_time", "event", "detail"])
+        self._events_file.flush()
+
+    def log_event(
+        self, event: str, detail: str = "", wall_time: Optional[float] = None
+    ) -> None:
+        if not self._events_writer:
+            return
+        if wall_time is None:
+            wall_time = time.time()
+        stamp = datetime.fromtimestamp(wall_time).isoformat(timespec="milliseconds")
+        self._events_writer.writerow([stamp, event, detail])
+        if self._events_file:
+            self._events_file.flush()
+
+    def mark_gap(self, lost_at: float, restored_at: float) -> None:
+        """Record a telemetry outage: a NaN row per device in the data CSV
+        (so plots break there) and a GAP entry in the events file."""
+        if not self._writer:
+            return
+        for device in list(self._current_t.keys()):
+            self._finalize_device_row(device)
+            self._current_row[device] = {}
+            if self._header_written:
+                nans = ["nan"] * len(self._columns)
+                self._buffer.append([device, self._current_t[device], *nans])
+        self.flush()
+        until = datetime.fromtimestamp(restored_at).isoformat(timespec="milliseconds")
+        self.log_event(
+            "GAP",
+            f"telemetry lost for {restored_at - lost_at:.1f} s, until {until}",
+            wall_time=lost_at,
+        )
+
+    def mark_t0(self, device_t: Optional[float], wall_time: float) -> None:
+        """Stamp ignition into the data CSV (t empty if the device clock is not
+        known yet) and into the events file with its wall-clock time."""
+        if not self._writer:
+            return
+        self.flush()
+        row = ["T0", "" if device_t is None else device_t]
+        if self._header_written:
+            self._buffer.append(row + [""] * len(self._columns))
+            self.flush()
+        else:
+            self._pending_markers.append(row)
+        shown = "unknown" if device_t is None else f"{device_t:.3f}"
+        self.log_event("T0", f"device_t={shown}", wall_time)
 
     def log(self, device: str, t: float, name: str, value: float) -> None:
         if not self._writer:
@@ -793,6 +3492,9 @@ class DataLogger(QObject):
             if self._file:
                 self._file.flush()
             self._header_written = True
+            for marker in self._pending_markers:
+                self._buffer.append(marker + [""] * len(self._columns))
+            self._pending_markers.clear()
         # Build row in fixed column order
         row = [device, t]
         for col in self._columns:
@@ -808,18 +3510,143 @@ class DataLogger(QObject):
         finally:
             if self._file:
                 self._file.close()
+            if self._events_file:
+                self._events_file.close()
             self._file = None
             self._writer = None
+            self._events_file = None
+            self._events_writer = None
             self.path = None
+            self.events_path = None
             self._columns = []
             self._current_t.clear()
             self._current_row.clear()
+            self._pending_markers.clear()
+
+
+# -----------------------------
+# Operator roles
+# -----------------------------
+# Established from the backend's /auth response ({"role": ...} or
+# {"roles": [...]}, highest wins). No or unknown role -> observer.
+ROLES = ("observer", "operator", "test_director")
+
+
+def role_from_auth(payload: Any) -> str:
+    if not isinstance(payload, dict):
+        return "observer"
+    names = payload.get("roles", [payload.get("role")])
+    if not isinstance(names, list):
+        names = [names]
+    granted = {re.sub(r"[\s-]+", "_", str(n).strip().lower()) for n in names}
+    ranks = [i for i, role in enumerate(ROLES) if role in granted]
+    return ROLES[max(ranks)] if ranks else "observer"
+
+
+# -----------------------------
+# Command audit trail
+# -----------------------------
+# One JSON object per line, e.g.
+#   {"seq": 7, "time": "...", "event": "command", ..., "mac": "hmac-sha256",
+#    "prev": "<hash of seq 6>", "hash": "<HMAC of this record without 'hash'>"}
+# Editing, removing or reordering any record breaks the chain, and without the
+# station's audit key (held in the CredentialStore) the chain cannot be
+# recomputed. A trail that ends without a "session_end" record was truncated
+# or the GUI crashed. If the key was unavailable the trail falls back to plain
+# SHA-256 ("mac": "sha256"), which the verifier never reports as trustworthy.
+AUDIT_GENESIS = "0" * 64
+
+
+def audit_hash(entry: dict[str, Any], key: Optional[bytes]) -> str:
+    body = {k: v for k, v in entry.items() if k != "hash"}
+    text = json.dumps(body, sort_keys=True, separators=(",", ":")).encode("utf-8")
+    if key is None:
+        return hashlib.sha256(text).hexdigest()
+    return hmac.new(key, text, hashlib.sha256).hexdigest()
+
+
+class AuditLog:
+    """Append-only, HMAC-chained JSON Lines file; every record is fsynced."""
+
+    def __init__(self, path: Path, key: Optional[bytes]) -> None:
+        path.parent.mkdir(parents=True, exist_ok=True)
+        self.path = path
+        self._key = key
+        self._mac = "sha256" if key is None else "hmac-sha256"
+        self._fh = open(path, "x", encoding="utf-8")  # never reopen a trail
+        self._prev = AUDIT_GENESIS
+        self._seq = 0
+        # Worker callbacks may arrive from the dispatcher thread
+        self._lock = threading.Lock()
+
+    def record(self, event: str, **fields: Any) -> int:
+        with self._lock:
+            self._seq += 1
+            entry = {
+                "seq": self._seq,
+                "time": datetime.now().isoformat(timespec="milliseconds"),
+                "event": event,
+                **fields,
+                "mac": self._mac,
+                "prev": self._prev,
+            }
+            entry["hash"] = audit_hash(entry, self._key)
+            self._fh.write(json.dumps(entry, separators=(",", ":")) + "\n")
+            self._fh.flush()
+            os.fsync(self._fh.fileno())
+            self._prev = entry["hash"]
+            return self._seq
+
+    def close(self) -> None:
+        self.record("session_end")
+        self._fh.close()
+
+
+def verify_audit_file(path: Path, key: Optional[bytes]) -> tuple[bool, str]:
+    """Check an audit trail's chain against the audit key; returns (ok, message).
+    A trail without a keyed MAC is never ok: anyone could have rewritten it."""
+    prev = AUDIT_GENESIS
+    last_event = None
+    keyed = True
+    with open(path, encoding="utf-8") as f:
+        for n, line in enumerate(f, 1):
+            try:
+                entry = json.loads(line)
+            except ValueError:
+                return False, f"line {n}: not valid JSON"
+            if not isinstance(entry, dict) or entry.get("seq") != n:
+                return False, f"line {n}: record missing or out of order"
+            if entry.get("prev") != prev:
+                return False, f"line {n}: chain broken (previous hash mismatch)"
+            if entry.get("mac") == "hmac-sha256":
+                if key is None:
+                    return False, "no audit key in this station's credential store"
+                expected = audit_hash(entry, key)
+            else:
+                keyed = False
+                expected = audit_hash(entry, None)
+            if not hmac.compare_digest(expected, str(entry.get("hash"))):
+                return False, f"line {n}: record altered or wrong key (MAC mismatch)"
+            prev = entry["hash"]
+            last_event = entry.get("event")
+    if last_event is None:
+        return False, "empty trail"
+    if not keyed:
+        return False, "chain intact but not keyed (audit key was unavailable)"
+    if last_event != "session_end":
+        # Records cut from the end leave a valid chain; so does a crash
+        return False, (
+            f"no session_end after line {n}: trail truncated, or the station "
+            "crashed before closing it"
+        )
+    return True, "chain intact"
 
 
 class MainWindow(QMainWindow):
-    def __init__(self, config: Config):
+    def __init__(self, config: Config, credentials: CredentialStore):
         super().__init__()
         self.config = config
+        self.credentials = credentials
         self.setWindowTitle("Prop Control")
 
         self.thread_pool = QThreadPool.globalInstance()
@@ -830,7 +3657,13 @@ class MainWindow(QMainWindow):
         self._http_workers: set[HttpRequestWorker] = (
             set()
         )  # keep refs to prevent GC/segfaults
+        # All backend commands go through one FIFO dispatcher
+        self.dispatcher = CommandDispatcher(self)
+        self.dispatcher.queueChanged.connect(self.on_command_queue_changed)
+        self.dispatcher.start()
         self.redis_thread: Optional[RedisTailer] = None
+        # Stopped threads that have not exited yet; kept alive until they do
+        self._retired_threads: set[QThread] = set()
 
         self._pending_points = []
         self._plot_timer = QTimer(self)
@@ -840,32 +3673,67 @@ class MainWindow(QMainWindow):
         self._last_render_time = 0  # throttle plot renders
 
         self.deviceConfig = None
+        self._key_armed = False  # fail-safe until the key switch says otherwise
+        self.role = "observer"  # read-only until /auth grants more
+        self.operator = ""
+        self.lease: Optional[ControlLease] = None
+        self.lease_held = False
+        self._lease_holder = ""  # "operator@host" of whoever holds it
+        self.hardwarePanel: Optional[HardwarePanel] = None
+        self._redis_state = "stopped"
+
+        self.command_tracker = CommandTracker(self.config.command_ack_timeout_s, self)
+        self.command_tracker.confirmed.connect(self.on_command_confirmed)
+        self.command_tracker.unconfirmed.connect(self.on_command_unconfirmed)
+        self.control_states: dict[str, ControlState] = {}
+        self._audit_ids: dict[str, Optional[int]] = {}  # control -> command seq
+        # Control -> pulse command, from queueing until its time is up
+        self._pulsing: dict[str, HttpRequestWorker] = {}
+        # Re-evaluate staleness even when no reports arrive
+        self._indicator_timer = QTimer(self)
+        self._indicator_timer.setInterval(1000)
+        self._indicator_timer.timeout.connect(self._refresh_control_indicators)
+        self._indicator_timer.start()
 
         # ----
         # Menu selections
         # ----
 
         server_menu = self.menuBar().addMenu("Server")
-        self.server_ips = [
-            "localhost",
-            "192.168.0.101",
-        ]
-        self.default_server_ip = self.server_ips[0]
-
-        for ip in self.server_ips:
-            act = QAction(ip, self)
-            act.triggered.connect(lambda checked, ip=ip: self.set_server_ip(ip))
+        self._profile_actions = QActionGroup(self)
+        self._profile_actions.setExclusive(True)
+        for name in self.config.profiles:
+            act = QAction(name, self, checkable=True)
+            act.setChecked(name == self.config.profile)
+            act.triggered.connect(lambda checked, n=name: self.set_profile(n))
+            self._profile_actions.addAction(act)
             server_menu.addAction(act)
 
         server_menu.addSeparator()
-        act_creds = QAction("Set API Credentials…", self)
+        act_creds = QAction("Set Credentials…", self)
         act_creds.triggered.connect(self.set_api_credentials)
         server_menu.addAction(act_creds)
 
+        # Control authority lease (one commanding station at a time)
+        control_menu = self.menuBar().addMenu("Control")
+        self.act_take_control = QAction("Take Control", self)
+        self.act_take_control.triggered.connect(self.take_control)
+        self.act_request_control = QAction("Request Control…", self)
+        self.act_request_control.triggered.connect(self.request_control)
+        self.act_release_control = QAction("Release Control", self)
+        self.act_release_control.triggered.connect(self.release_control)
+        for act in (
+            self.act_take_control,
+            self.act_request_control,
+            self.act_release_control,
+        ):
+            control_menu.addAction(act)
+
         # --- Logging setup ---
-        self._log_dir = Path(__file__).resolve().parent / "data"
+        self._log_dir = self._resolve_log_dir()
         self._test_start_dt: Optional[datetime] = None
         self.logger = DataLogger(self)
+        self.audit: Optional[AuditLog] = None  # opened with the first command
 
         self._log_timer = QTimer(self)
         self._log_timer.setInterval(1000)  # flush once per second
@@ -878,6 +3746,10 @@ class MainWindow(QMainWindow):
         self.act_show_log = QAction("Show Log", self, checkable=True)
         self.act_show_log.setChecked(True)
         view_menu.addAction(self.act_show_log)
+        self.act_show_sequencer = QAction("Show Sequencer", self, checkable=True)
+        view_menu.addAction(self.act_show_sequencer)
+        self.act_show_countdown = QAction("Show Countdown", self, checkable=True)
+        view_menu.addAction(self.act_show_countdown)
 
         # ----
         # Controls (moved into sidebar General group)
@@ -903,24 +3775,102 @@ class MainWindow(QMainWindow):
         graphs_scroll.setWidgetResizable(True)
         graphs_scroll.setMinimumHeight(300)
 
+        # P&ID diagram shares the space with the graphs
+        self.pid_panel = PidPanel()
+        self.pid_panel.controlRequested.connect(self.request_control)
+        self.graphs.readoutChanged.connect(self.pid_panel.set_readout)
+        self.view_tabs = QTabWidget()
+        self.view_tabs.addTab(graphs_scroll, "Graphs")
+        self.view_tabs.addTab(self.pid_panel, "P&&ID")
+        self.load_pid()
+
         # Right stack: splitter between graphs and log
         right_splitter = QSplitter(Qt.Orientation.Vertical)
-        right_splitter.addWidget(graphs_scroll)
+        right_splitter.addWidget(self.view_tabs)
         right_splitter.addWidget(self.log)
         right_splitter.setSizes([500, 200])
 
         right_container = QWidget()
         right_v = QVBoxLayout(right_container)
+        self.alarm_banner = AlarmBanner()
+        self.alarm_banner.acknowledged.connect(self.on_alarm_acknowledged)
+        right_v.addWidget(self.alarm_banner)
+
+        # Test sequencer (hidden until enabled from the View menu)
+        # Series and bare name -> (value, monotonic arrival time)
+        self.latest_values: dict[str, tuple[float, float]] = {}
+        self.sequencer = Sequencer(
+            lambda control, action: self.send_control_command(control, action),
+            self.latest_value,
+            self._commanding_allowed,
+            self,
+        )
+        self.sequencer.message.connect(lambda m: self.append_log(f"[SEQ] {m}"))
+        self.sequencer.abortRequested.connect(self.run_safing)
+        self.sequencer_panel = SequencerPanel(self.sequencer)
+        self.sequencer_panel.setVisible(False)
+        self.act_show_sequencer.toggled.connect(self.sequencer_panel.setVisible)
+        right_v.addWidget(self.sequencer_panel)
+
+        # Ignition countdown (hidden until enabled from the View menu)
+        self._last_sample_t: Optional[float] = None  # device time of last sample
+        self._device_t: dict[str, float] = {}  # device -> time of its last sample
+        self._echo_t: dict[str, float] = {}  # series -> time of its last echo
+        self._go_conditions = [Condition.from_dict(d) for d in config.go_conditions]
+        self.countdown = Countdown(self._go_checks, self)
+        self.countdown.configure(config.countdown_s, config.terminal_count_s)
+        self.countdown.message.connect(self.on_countdown_message)
+        self.countdown.ignition.connect(self.on_ignition)
+        self.countdown_panel = CountdownPanel(self.countdown, config.go_polls)
+        self.countdown_panel.setVisible(False)
+        self.act_show_countdown.toggled.connect(self.countdown_panel.setVisible)
+        right_v.addWidget(self.countdown_panel)
         right_v.addWidget(right_splitter, 1)
 
         # Left side: controls sidebar + (optional) config panel
         side = QWidget()
         self._side_v = QVBoxLayout(side)
+        # Always-enabled abort button above the (rebuilt) controls sidebar
+        self.btn_abort = QPushButton("ABORT")
+        self.btn_abort.setToolTip(
+            f"Run the safing sequence ({self.config.abort_hotkey})"
+        )
+        self.btn_abort.setMinimumHeight(48)
+        self.btn_abort.setStyleSheet(
+            "QPushButton { background-color: #c0392b; color: white;"
+            " font-size: 16pt; font-weight: bold; }"
+        )
+        self.btn_abort.clicked.connect(lambda: self.run_safing("ABORT button"))
+        self._side_v.addWidget(self.btn_abort)
+        abort_shortcut = QShortcut(QKeySequence(self.config.abort_hotkey), self)
+        abort_shortcut.setContext(Qt.ShortcutContext.ApplicationShortcut)
+        abort_shortcut.activated.connect(self.on_abort_hotkey)
+
+        self.abort_sequence = AbortSequence(
+            self.config.abort_sequence,
+            lambda control, action: self.send_control_command(
+                control, action, CommandPriority.SAFING, preempt=True
+            ),
+            self,
+        )
+        self.abort_sequence.stepStarted.connect(
+            lambda i, n, desc: self.append_log(f"ABORT step {i}/{n}: {desc}")
+        )
+        self.abort_sequence.finished.connect(
+            lambda: self.append_log("ABORT sequence complete")
+        )
+        self.redlines = RedlineMonitor(self.config.redlines)
+        self.interlocks = [Interlock.from_dict(d) for d in self.config.interlocks]
+
         # Start with an empty sidebar; rebuilt dynamically from server config
         self.controls_sidebar = ControlsSidebar(general_widget=self.controls_panel)
         self.controlButtons = self.controls_sidebar.controlButtons
-        self.controls_sidebar.controlRequested.connect(self.send_control_command)
+        self.controls_sidebar.controlRequested.connect(self.request_control)
+        self.controls_sidebar.setpointRequested.connect(self.send_setpoint)
+        self.controls_sidebar.pulseRequested.connect(self.send_pulse)
+        self.controls_sidebar.closeAllRequested.connect(self.close_all)
         self._side_v.addWidget(self.controls_sidebar)
+        self._update_control_enablement()
         self._side_v.addStretch(1)
         side.setMinimumWidth(220)
         side.setMaximumWidth(220)
@@ -942,30 +3892,227 @@ class MainWindow(QMainWindow):
         self.btn_stream.clicked.connect(self.on_stream)
         self.btn_stop.clicked.connect(self.on_stop)
 
+        self.load_credentials()
+
         # Server setup - defer to event loop to avoid blocking on Redis connection
-        QTimer.singleShot(100, lambda: self.set_server_ip(self.default_server_ip))
+        self.append_log(
+            f"Backend {self.config.api_base}, "
+            f"Redis {self.config.redis_host}:{self.config.redis_port}"
+        )
+        QTimer.singleShot(100, self.start_redis)
         QTimer.singleShot(100, self.send_config_request)
         QTimer.singleShot(100, self.send_status_request)
 
-        try:
-            # Keyswitch setup
-            self.keySwitchMonitor = keySwitchMonitor("COM3")
-            self.keySwitchMonitor.statusSig.connect(self.handleKeySwitch)
-            self.keySwitchMonitor.messageSig.connect(lambda msg: self.append_log(msg))
-            self.keySwitchMonitor.start()
-            self.append_log(f"KEY SWITCH STATUS {str(self.keySwitchMonitor.status)}")
-        except serial.SerialException:
-            self.append_log("Failed to initialize key switch monitor")
-
+        self.role_label = QLabel("")
+        self.statusBar().addPermanentWidget(self.role_label)
+        self.lease_label = QLabel("")
+        self.statusBar().addPermanentWidget(self.lease_label)
+        self.login()
+        QTimer.singleShot(100, self.start_lease)
+
+        self.panel_label = QLabel("Panel: not connected")
+        self.statusBar().addPermanentWidget(self.panel_label)
+        self.start_hardware_panel()
+
+        self._update_window_title()
+        self.redis_label = QLabel("Redis: stopped")
+        self.statusBar().addPermanentWidget(self.redis_label)
+        self.queue_label = QLabel("Commands: idle")
+        self.statusBar().addPermanentWidget(self.queue_label)
         self.statusBar().showMessage("Ready")
 
+    def start_hardware_panel(self) -> None:
+        # Keyswitch setup; the monitor finds and (re)opens the port itself
+        self.hardwarePanel = HardwarePanel(
+            self.config.key_switch_port,
+            usb_ids=self.config.key_switch_usb_ids,
+            serial_number=self.config.key_switch_serial,
+            # Without a required key switch a silent panel locks nothing
+            timeout_s=(
+                self.config.key_switch_timeout_s
+                if self.config.key_switch_required
+                else 0.0
+            ),
+        )
+        self.hardwarePanel.statusSig.connect(self.handleKeySwitch)
+        self.hardwarePanel.watchdogSig.connect(self.on_panel_watchdog)
+        self.hardwarePanel.inputSig.connect(self.on_panel_input)
+        self.hardwarePanel.messageSig.connect(lambda msg: self.append_log(msg))
+        self.hardwarePanel.connectionSig.connect(self.on_panel_connection)
+        # A fresh driver starts with everything off; give it the current state
+        self.set_panel_output("ARMED", self._key_armed)
+        self.set_panel_output("STREAMING", self.logger.is_active)
+        alarm = self.alarm_banner.isVisible()
+        self.set_panel_output("ALARM", alarm)
+        self.set_panel_output("BUZZER", alarm)
+        self.set_panel_output("LINK_LOST", self._redis_state != "connected")
+        self.hardwarePanel.start()
+
+    @Slot(bool, str)
+    def on_panel_connection(self, connected: bool, where: str) -> None:
+        if connected:
+            self.panel_label.setText(f"Panel: {where}")
+            self.panel_label.setStyleSheet("color: green; font-weight: bold;")
+            self.append_log(f"Panel connected on {where}")
+        else:
+            self.panel_label.setText("Panel: not connected")
+            self.panel_label.setStyleSheet("color: red; font-weight: bold;")
+            self.panel_label.setToolTip(where)
+            self.append_log(f"Panel not connected ({where})")
+
+    def stop_hardware_panel(self) -> None:
+        if self.hardwarePanel is not None:
+            self.hardwarePanel.stop()
+            self.hardwarePanel.wait(2000)
+            self.hardwarePanel = None
+
     def handleKeySwitch(self, status: int):
         self.append_log(f"Keyswitch changed to {status} position")
-        enabled = status == 1
-        for button in self.controlButtons.values():
-            button.setEnabled(enabled)
-        self.controls_sidebar.btn_close_all.setEnabled(enabled)
-        self.controls_sidebar.btn_default_positions.setEnabled(enabled)
+        self._key_armed = status == 1
+        self._update_control_enablement()
+
+    @Slot(bool)
+    def on_panel_watchdog(self, expired: bool) -> None:
+        if expired:
+            self._key_armed = False
+            self._update_control_enablement()
+            self.raise_alarm(
+                f"Panel silent for {self.config.key_switch_timeout_s:g} s: "
+                "treating as DISARMED, controls locked"
+            )
+        else:
+            self.append_log("Panel heartbeat restored")
+
+    @Slot(str, int)
+    def on_panel_input(self, name: str, value: int) -> None:
+        self.append_log(f"Panel input {name} = {value}")
+        if name == "ABORT" and value == 1:
+            self.raise_alarm("Hardware ABORT button pressed")
+            self.run_safing("hardware abort button")
+
+    def set_panel_output(self, name: str, on: bool) -> None:
+        if self.hardwarePanel is not None:
+            self.hardwarePanel.set_output(name, on)
+
+    def _has_authority(self) -> bool:
+        return self.lease_held or not self.config.lease_required
+
+    def _commanding_allowed(self) -> bool:
+        armed = self._key_armed or not self.config.key_switch_required
+        return armed and self.role != "observer" and self._has_authority()
+
+    def _control_spec(self, name: str) -> ControlSpec:
+        """Sidebar metadata, or the legacy defaults before /config arrives."""
+        name = name.upper()
+        spec = self.controls_sidebar.specs.get(name)
+        return spec if spec is not None else ControlSpec.from_config(name, {})
+
+    def _critical_controls(self) -> set[str]:
+        """Controls the current role may not command."""
+        if self.role == "test_director":
+            return set()
+        names = set(self.controls_sidebar.specs) | {
+            n.upper() for n in self.config.pid_valves
+        }
+        for step in self.config.firing_sequence:
+            if step.get("type") == "command":
+                names.add(str(step["control"]).upper())
+        return {n for n in names if self._control_spec(n).danger == "critical"}
+
+    def _update_control_enablement(self) -> None:
+        """Single place that decides whether commanding widgets are usable."""
+        allowed = self._commanding_allowed()
+        locked = self._critical_controls()
+        self.controls_sidebar.set_armed(allowed)
+        self.controls_sidebar.set_locked_controls(locked)
+        self.pid_panel.set_armed(allowed)
+        self.pid_panel.set_locked_controls(locked)
+        # Observer sessions are read-only; without the lease only safing is
+        # left. ABORT and its hotkey stay enabled for every role.
+        read_only = self.role == "observer"
+        self.controls_panel.setEnabled(not read_only and self._has_authority())
+        self.sequencer_panel.setEnabled(not read_only)
+        self.countdown_panel.setEnabled(not read_only)
+        self.set_panel_output("ARMED", self._key_armed)
+        lease_idle = self.lease is not None and not read_only
+        self.act_take_control.setEnabled(
+            lease_idle and not self.lease_held and not self._lease_holder
+        )
+        self.act_request_control.setEnabled(
+            lease_idle and not self.lease_held and bool(self._lease_holder)
+        )
+        self.act_release_control.setEnabled(self.lease_held)
+
+    def _refusal(self, name: str) -> Optional[str]:
+        """Why the role or lease forbids commanding a control, or None."""
+        if self.role == "observer":
+            return "observer session is read-only"
+        if not self._has_authority():
+            return f"control lease held by {self._lease_holder or 'nobody'}"
+        critical = self._control_spec(name).danger == "critical"
+        if critical and self.role != "test_director":
+            return "critical control requires the test director role"
+        return None
+
+    def _authorized(self, name: str, command: str) -> bool:
+        """Role and lease check for a single control command; logs refusals."""
+        reason = self._refusal(name)
+        if reason is None:
+            return True
+        self.append_log(f"REFUSED: {command} {name}, {reason}")
+        self.statusBar().showMessage(f"{command} {name} refused: {reason}", 10000)
+        return False
+
+    def set_role(self, role: str, operator: str) -> None:
+        if (role, operator) != (self.role, self.operator):
+            who = f" ({operator})" if operator else ""
+            self.append_log(f"Role: {role}{who}")
+        self.role = role
+        self.operator = operator
+        if self.lease is not None:
+            self.lease.operator = operator
+            if role == "observer" and self.lease_held:
+                self.lease.release()
+        self.role_label.setText(f"Role: {role.replace('_', ' ')}")
+        color = {"observer": "gray", "test_director": "#c62828"}.get(role, "green")
+        self.role_label.setStyleSheet(f"color: {color}; font-weight: bold;")
+        self._update_control_enablement()
+
+    def raise_alarm(self, text: str) -> None:
+        self.append_log(f"ALARM: {text}")
+        self.alarm_banner.raise_alarm(text)
+        self.set_panel_output("ALARM", True)
+        self.set_panel_output("BUZZER", True)
+
+    @Slot()
+    def on_alarm_acknowledged(self) -> None:
+        self.append_log("Alarms acknowledged")
+        self.set_panel_output("ALARM", False)
+        self.set_panel_output("BUZZER", False)
+
+    @Slot()
+    def on_abort_hotkey(self) -> None:
+        self.run_safing("ABORT hotkey")
+
+    def run_safing(self, reason: str) -> None:
+        """Put the stand in a safe state with the configured abort sequence
+        (or Close All if none is configured), ahead of anything queued.
+        Runs regardless of the key switch, lease and role: redlines, the panel
+        ABORT input and the sequencer must be able to safe the stand even before
+        /auth has answered."""
+        self.append_log(f"SAFING ({reason})")
+        if self.sequencer.state in ("running", "holding"):
+            self.sequencer.stop()
+            self.append_log("[SEQ] Sequence stopped by safing")
+        if self.countdown.state != "idle":
+            self.countdown.recycle(f"safing ({reason})")
+        if not self.abort_sequence.steps:
+            self.append_log("No abort_sequence configured; closing all valves")
+            self.close_all(self.controls_sidebar.close_all_controls)
+            return
+        if self.abort_sequence.running:
+            self.append_log("ABORT sequence restarted")
+        self.abort_sequence.start()
 
     def _collect_sensor_columns(self) -> list[str]:
         """Collect sensor names from deviceConfig for CSV header order.
@@ -1000,45 +4147,176 @@ class MainWindow(QMainWindow):
             path = "/" + path
         return base.rstrip("/") + path
 
-    def set_server_ip(self, ip: str):
-        self.config.redis_host = ip
-        # Update API base as well:
-        self.config.api_base = f"http://{ip}:8000"
-        self.append_log(f"Server IP set to {ip}")
+    def _resolve_log_dir(self) -> Path:
+        app_dir = Path(__file__).resolve().parent
+        if not self.config.log_dir:
+            return app_dir / "data"
+        return app_dir / Path(self.config.log_dir).expanduser()
+
+    def load_pid(self) -> None:
+        if not self.config.pid_svg:
+            return
+        path = Path(__file__).resolve().parent / Path(self.config.pid_svg).expanduser()
+        try:
+            missing = self.pid_panel.load(
+                path, self.config.pid_valves, self.config.pid_sensors
+            )
+        except ValueError as e:
+            self.append_log(f"P&ID ERROR: {e}")
+            return
+        if missing:
+            self.append_log(f"P&ID: element(s) not found: {', '.join(missing)}")
+
+    def _update_window_title(self) -> None:
+        self.setWindowTitle(f"Prop Control [{self.config.profile}]")
+
+    def set_profile(self, name: str) -> None:
+        """Switch backend, Redis, key switch and logging to another station profile.
+        Everything tied to the old profile is torn down before the new one starts."""
+        if self.logger.is_active:
+            QMessageBox.warning(
+                self, "Profile", "Stop the current test log before switching profile."
+            )
+            self._check_profile_action(self.config.profile)
+            return
+
+        old_panel = (self.config.key_switch_port, self.config.key_switch_required)
+        self.stop_redis()
+        self.stop_lease()
+        try:
+            self.config.apply_profile(name)
+        except ValueError as e:
+            self.append_log(f"Profile ERROR: {e}")
+            self._check_profile_action(self.config.profile)
+            self.start_redis()
+            self.start_lease()
+            return
+
+        self.close_audit()  # the next command starts a trail in the new log dir
+        self._log_dir = self._resolve_log_dir()
+        self._update_window_title()
+        self.load_credentials()
+        self.login()
+        self.append_log(
+            f"Profile '{name}': backend {self.config.api_base}, "
+            f"Redis {self.config.redis_host}:{self.config.redis_port}, "
+            f"logs in {self._log_dir}"
+        )
+
+        # Controls from the previous backend no longer apply
+        self.deviceConfig = None
+        self.command_tracker.clear()
+        self.control_states.clear()
+        self.latest_values.clear()
+        self._device_t.clear()
+        self._echo_t.clear()
+        self._rebuild_controls_sidebar([])
+
+        if (self.config.key_switch_port, self.config.key_switch_required) != old_panel:
+            self.stop_hardware_panel()
+            self._key_armed = False
+            self.start_hardware_panel()
+        self._update_control_enablement()
+
         self.start_redis()
+        self.start_lease()
+        self.send_config_request()
+        self.send_status_request()
+
+    def _check_profile_action(self, name: str) -> None:
+        for act in self._profile_actions.actions():
+            act.setChecked(act.text() == name)
+
+    def load_credentials(self) -> None:
+        """Fill the runtime credentials for the active profile from the store."""
+        try:
+            creds = self.credentials.load(self.config.profile)
+        except CredentialStoreLocked:
+            creds = {k: "" for k in CREDENTIAL_KEYS}
+            self.append_log("Credential store locked; no stored credentials loaded")
+        except Exception as e:
+            creds = {k: "" for k in CREDENTIAL_KEYS}
+            self.append_log(f"Credential store ERROR: {e}")
+        self.config.update(creds)
+        if not creds["api_username"]:
+            self.append_log(
+                f"No API credentials stored for profile '{self.config.profile}'"
+            )
 
     def set_api_credentials(self):
+        if self.credentials.needs_unlock:
+            unlock_credential_store(self.credentials)
+            if self.credentials.needs_unlock:
+                return
         dlg = CredentialsDialog(
-            self.config.api_username, self.config.api_password, self
+            self.config.credentials(),
+            self.config.profile,
+            self.credentials.backend_name,
+            self,
         )
         if dlg.exec() != QDialog.DialogCode.Accepted:
             return
-        user, pwd = dlg.values()
-        if not user or not pwd:
+        creds = dlg.values()
+        if not creds["api_username"] or not creds["api_password"]:
             QMessageBox.warning(
-                self, "API Credentials", "Username and password cannot be empty."
+                self, "Credentials", "API username and password cannot be empty."
             )
             return
-        self.validate_auth(user, pwd)
-
-    def validate_auth(self, user: str, pwd: str) -> None:
+        self.validate_auth(creds)
+
+    def login(self) -> None:
+        """Establish the role for the active profile's stored credentials;
+        read-only until the backend confirms them."""
+        self.set_role("observer", "")
+        creds = self.config.credentials()
+        if creds["api_username"]:
+            self.validate_auth(creds, store=False)
+
+    def validate_auth(self, creds: dict[str, str], store: bool = True) -> None:
+        """Check creds against /auth and adopt the role it grants; with store,
+        also save them for the profile (new credentials from the dialog)."""
         base = (
             self.config.api_base.strip().rstrip("/")
             or f"http://{self.config.redis_host}:8000"
         )
         url = base + "/auth"
         self.append_log(f"Validating credentials at {url}")
-        worker = HttpRequestWorker("GET", url, auth=(user, pwd))
+        worker = HttpRequestWorker(
+            "GET", url, auth=(creds["api_username"], creds["api_password"])
+        )
+        profile = self.config.profile
 
-        def on_ok(_payload: object) -> None:
-            # Save only on success
-            self.config.api_username = user
-            self.config.api_password = pwd
+        def on_ok(payload: object) -> None:
+            # Save only on success, and only if the profile did not change meanwhile
+            if profile != self.config.profile:
+                return
+            self.set_role(role_from_auth(payload), creds["api_username"])
+            if not store:
+                return
+            redis_changed = (creds["redis_username"], creds["redis_password"]) != (
+                self.config.redis_username,
+                self.config.redis_password,
+            )
+            try:
+                self.credentials.save(profile, creds)
+            except Exception as e:
+                self.append_log(f"Credential store ERROR: {e}")
+                QMessageBox.warning(
+                    self, "Credentials", f"Could not store credentials:\n{e}"
+                )
+            self.config.update(creds)
             self.append_log("Auth OK: credentials accepted")
             self.statusBar().showMessage("Auth OK", 3000)
+            if redis_changed:
+                self.stop_redis()
+                self.start_redis()
+                self.stop_lease()
+                self.start_lease()
 
         def on_err(msg: str) -> None:
             self.append_log(f"Auth ERROR: {msg}")
+            if not store:
+                return  # stay an observer; no dialog at startup
             QMessageBox.warning(
                 self, "Authentication Failed", f"/auth check failed:\n{msg}"
             )
@@ -1120,24 +4398,24 @@ class MainWindow(QMainWindow):
                     self.controlButtons[f"{control}_open"].setEnabled(True)
                     self.controlButtons[f"{control}_close"].setEnabled(False)
 
-    def _extract_controls_from_config(self) -> list[str]:
-        """Return all unique control names (uppercased) from the device config."""
+    def _extract_controls_from_config(self) -> list[ControlSpec]:
+        """Return one spec per unique control (uppercased) in the device config."""
         seen: set[str] = set()
-        controls: list[str] = []
+        controls: list[ControlSpec] = []
         if not isinstance(self.deviceConfig, dict):
             return controls
         for devDict in self.deviceConfig.get("configs", {}).values():
             if not isinstance(devDict, dict):
                 continue
-            for ctrl in devDict.get("controls", {}).keys():
+            for ctrl, meta in devDict.get("controls", {}).items():
                 name = ctrl.upper()
                 if name not in seen:
                     seen.add(name)
-                    controls.append(name)
+                    controls.append(ControlSpec.from_config(name, meta))
         return controls
 
-    def _rebuild_controls_sidebar(self, controls: list[str]) -> None:
-        """Replace the controls sidebar with one built from the given control names."""
+    def _rebuild_controls_sidebar(self, controls: list[ControlSpec]) -> None:
+        """Replace the controls sidebar with one built from the given control specs."""
         # Detach controls_panel from the old sidebar before destroying it
         self.controls_panel.setParent(None)
 
@@ -1151,14 +4429,19 @@ class MainWindow(QMainWindow):
             controls=controls,
         )
         self.controlButtons = self.controls_sidebar.controlButtons
-        self.controls_sidebar.controlRequested.connect(self.send_control_command)
-        # Insert before the trailing stretch (index 0)
-        self._side_v.insertWidget(0, self.controls_sidebar)
+        self.controls_sidebar.controlRequested.connect(self.request_control)
+        self.controls_sidebar.setpointRequested.connect(self.send_setpoint)
+        self.controls_sidebar.pulseRequested.connect(self.send_pulse)
+        self.controls_sidebar.closeAllRequested.connect(self.close_all)
+        # Insert below the abort button, before the trailing stretch
+        self._side_v.insertWidget(1, self.controls_sidebar)
+        self.pid_panel.set_analog_controls(
+            {spec.name for spec in controls if spec.kind == "analog"}
+        )
 
-        # Reapply key switch state if the monitor is running
-        ks = getattr(self, "keySwitchMonitor", None)
-        if ks is not None and ks.status is not None:
-            self.handleKeySwitch(ks.status)
+        # New widgets start enabled; reapply the key switch lock
+        self._update_control_enablement()
+        self._refresh_control_indicators()
 
     def handleConfigResponse(self, payload: dict) -> None:
         self.append_log(f"Config response: {payload}")
@@ -1170,7 +4453,7 @@ class MainWindow(QMainWindow):
 
             # Rebuild the sidebar from whatever controls the server declares
             controls = self._extract_controls_from_config()
-            self.append_log(f"Controls from config: {controls}")
+            self.append_log(f"Controls from config: {[c.name for c in controls]}")
             self._rebuild_controls_sidebar(controls)
 
             # Set button states based on default states from config
@@ -1245,23 +4528,105 @@ class MainWindow(QMainWindow):
         except Exception as e:
             self.append_log(f"Failed to send status request: {e}")
 
-    def send_command(self, payload: dict[str, Any]) -> HttpRequestWorker:
+    def send_command(
+        self,
+        payload: dict[str, Any],
+        priority: CommandPriority = CommandPriority.NORMAL,
+        preempt: bool = False,
+        prepare: Optional[Callable[[HttpRequestWorker], None]] = None,
+    ) -> HttpRequestWorker:
+        """Queue a command for the dispatcher. SAFING priority jumps the queue;
+        preempt=True also cancels every queued NORMAL command. prepare(worker)
+        runs before the worker is queued, so its signal handlers see every
+        outcome, even an immediate failure."""
         url = self.build_url()
         _, auth = self._base_and_auth()
-        self.append_log(f"POST {url} -> {payload}")
+        tag = " [SAFING]" if priority == CommandPriority.SAFING else ""
+        self.append_log(f"POST {url} -> {payload}{tag}")
         worker = HttpRequestWorker("POST", url, json_body=payload, auth=auth)
+        worker.audit_id = self.audit_record(
+            "command",
+            payload=payload,
+            url=url,
+            priority=priority.name,
+            operator=self.operator,
+            role=self.role,
+            os_user=getpass.getuser(),
+            key_armed=self._key_armed,
+            key_switch_required=self.config.key_switch_required,
+        )
+
+        def _audit_result(**result: Any) -> None:
+            # Round trip of the request itself, not its time in the queue;
+            # commands cancelled while queued were never sent
+            if worker.sent_at is not None and worker.replied_at is not None:
+                latency = worker.replied_at - worker.sent_at
+                result["latency_ms"] = round(latency * 1000, 1)
+            self.audit_record("result", command=worker.audit_id, **result)
+
         worker.signals.success.connect(
             lambda resp: self.append_log(f"Command OK: {resp}")
         )
+        worker.signals.success.connect(
+            lambda resp: _audit_result(ok=True, response=str(resp)[:500])
+        )
         worker.signals.error.connect(
             lambda msg: self.append_log(f"Command ERROR: {msg}")
         )
+        worker.signals.error.connect(lambda msg: _audit_result(ok=False, error=msg))
         # track worker to prevent premature GC while running
         self._http_workers.add(worker)
         worker.signals.finished.connect(lambda: self._http_workers.discard(worker))
-        self.thread_pool.start(worker)
+        if prepare is not None:
+            prepare(worker)
+        self.dispatcher.submit(worker, priority, preempt)
         return worker
 
+    def audit_record(self, event: str, **fields: Any) -> Optional[int]:
+        """Append to this session's audit trail; returns the record's seq."""
+        try:
+            if self.audit is None:
+                stamp = datetime.now().strftime("%Y%m%d-%H%M%S")
+                self.audit = AuditLog(
+                    self._log_dir / f"audit-{stamp}.jsonl", self._audit_key()
+                )
+                self.audit.record(
+                    "session_start",
+                    profile=self.config.profile,
+                    api_base=self.config.api_base,
+                    host=socket.gethostname(),
+                    os_user=getpass.getuser(),
+                )
+                self.append_log(f"Audit trail: {self.audit.path}")
+            return self.audit.record(event, **fields)
+        except OSError as e:
+            self.append_log(f"AUDIT ERROR: {e}")
+            return None
+
+    def _audit_key(self) -> Optional[bytes]:
+        try:
+            return self.credentials.audit_key()
+        except Exception as e:
+            self.append_log(f"AUDIT WARNING: no audit key ({e}); trail is unkeyed")
+            return None
+
+    def close_audit(self) -> None:
+        if self.audit is None:
+            return
+        try:
+            self.audit.close()
+        except OSError as e:
+            self.append_log(f"AUDIT ERROR: {e}")
+        self.audit = None
+
+    @Slot(int, bool)
+    def on_command_queue_changed(self, depth: int, in_flight: bool) -> None:
+        if not depth and not in_flight:
+            self.queue_label.setText("Commands: idle")
+        else:
+            busy = ", 1 in flight" if in_flight else ""
+            self.queue_label.setText(f"Commands: {depth} queued{busy}")
+
     @Slot()
     def start_redis(self) -> None:
         if self.redis_thread and self.redis_thread.isRunning():
@@ -1272,33 +4637,185 @@ class MainWindow(QMainWindow):
             chan = self.config.redis_channel
             user = self.config.redis_username
             pwd = self.config.redis_password
+            mode = self.config.redis_mode
+            if mode not in {"pubsub", "stream"}:
+                raise ValueError(mode)
         except Exception:
-            QMessageBox.warning(self, "Redis", "Invalid host/port/channel/user/pass")
+            QMessageBox.warning(
+                self, "Redis", "Invalid host/port/channel/mode/user/pass"
+            )
             return
 
-        self.redis_thread = RedisTailer(host, port, chan, user, pwd)
-        self.redis_thread.message.connect(lambda m: self.append_log(f"[redis] {m}"))
-        self.redis_thread.message.connect(self.on_redis_message)
+        self.redis_thread = RedisTailer(
+            host, port, chan, user, pwd, mode=mode, stream=self.config.redis_stream
+        )
+        self.redis_thread.batch.connect(self.on_redis_batch)
         self.redis_thread.status.connect(self.append_log)
         self.redis_thread.error.connect(lambda e: self.append_log(f"Redis ERROR: {e}"))
+        self.redis_thread.connectionState.connect(self.on_redis_state)
+        self.redis_thread.gap.connect(self.on_redis_gap)
         self.redis_thread.start()
 
+    def start_lease(self) -> None:
+        if not self.config.lease_required:
+            self.lease_label.setText("Lease: not required")
+            self._update_control_enablement()
+            return
+        if self.lease is not None:
+            return
+        self.lease = ControlLease(
+            self.config.redis_host,
+            int(self.config.redis_port),
+            self.config.redis_username,
+            self.config.redis_password,
+            self.config.lease_key,
+            self.config.lease_ttl_s,
+        )
+        self.lease.operator = self.operator
+        self.lease.leaseChanged.connect(self.on_lease_changed)
+        self.lease.handoverRequested.connect(self.on_handover_requested)
+        self.lease.message.connect(self.on_lease_message)
+        self.lease.error.connect(lambda e: self.append_log(f"Lease ERROR: {e}"))
+        self.lease.start()
+        self.on_lease_changed(False, "")
+
+    def stop_lease(self) -> None:
+        lease = self.lease
+        if lease is None:
+            return
+        self.lease = None
+        lease.leaseChanged.disconnect()
+        lease.handoverRequested.disconnect()
+        lease.stop()  # releases the lease if we hold it
+        if not lease.wait(3000):
+            self._retired_threads.add(lease)
+            lease.finished.connect(lambda t=lease: self._retired_threads.discard(t))
+        self.on_lease_changed(False, "")
+
+    @Slot(bool, str)
+    def on_lease_changed(self, held: bool, holder: str) -> None:
+        if (held, holder) != (self.lease_held, self._lease_holder):
+            self.audit_record("lease", held=held, holder=holder)
+        self.lease_held = held
+        self._lease_holder = holder
+        if held:
+            text, color = "Lease: this station", "green"
+        elif holder:
+            text, color = f"Lease: held by {holder}", "#c62828"
+        else:
+            text, color = "Lease: free", "#b58900"
+        self.lease_label.setText(text)
+        self.lease_label.setStyleSheet(f"color: {color}; font-weight: bold;")
+        self._update_control_enablement()
+
+    @Slot(str)
+    def on_lease_message(self, text: str) -> None:
+        self.append_log(f"[LEASE] {text}")
+        self.audit_record("lease", message=text)
+
+    @Slot(str, str)
+    def on_handover_requested(self, requester: str, request: str) -> None:
+        self.append_log(f"[LEASE] {requester} requests control")
+        answer = QMessageBox.question(
+            self,
+            "Control Request",
+            f"{requester} requests control authority.\n\n"
+            "Hand over? This station will lose control immediately.",
+            QMessageBox.StandardButton.Yes | QMessageBox.StandardButton.No,
+            QMessageBox.StandardButton.No,
+        )
+        if self.lease is None:
+            return
+        if answer == QMessageBox.StandardButton.Yes:
+            self.lease.grant(request)
+        else:
+            self.lease.deny(request)
+
+    def take_control(self) -> None:
+        if self.lease is not None and self.role != "observer":
+            self.lease.acquire()
+
+    def request_control(self) -> None:
+        if self.lease is not None and self.role != "observer":
+            self.lease.request()
+
+    def release_control(self) -> None:
+        if self.lease is not None:
+            self.lease.release()
+
+    @Slot(str)
+    def on_redis_state(self, state: str) -> None:
+        colors = {"connected": "green", "reconnecting": "red", "connecting": "#b58900"}
+        color = colors.get(state, "gray")
+        self._redis_state = state
+        self.redis_label.setText(f"Redis: {state}")
+        self.redis_label.setStyleSheet(f"color: {color}; font-weight: bold;")
+        self.set_panel_output("LINK_LOST", state != "connected")
+
+    @Slot(float, float)
+    def on_redis_gap(self, lost_at: float, restored_at: float) -> None:
+        self.append_log(f"Redis link restored after {restored_at - lost_at:.1f} s gap")
+        self.graphs.mark_gap()
+        if self.logger.is_active:
+            self.logger.mark_gap(lost_at, restored_at)
+
     @Slot()
     def stop_redis(self) -> None:
-        if self.redis_thread and self.redis_thread.isRunning():
-            self.redis_thread.stop()
-            self.redis_thread.wait(2000)
+        thread = self.redis_thread
+        if thread is None:
+            return
+        self.redis_thread = None
+        # Nothing from the old connection should reach the UI after this point
+        thread.batch.disconnect()
+        thread.connectionState.disconnect()
+        thread.gap.disconnect()
+        self.on_redis_state("stopped")
+        thread.stop()
+        if not thread.wait(3000):
+            # Still stuck in a connect timeout; keep a ref until it finishes
+            self._retired_threads.add(thread)
+            thread.finished.connect(lambda t=thread: self._retired_threads.discard(t))
+
+    def close_all(self, controls: list[str]) -> None:
+        """Close the given controls ahead of (and instead of) anything queued."""
+        self.append_log(f"Close All: {', '.join(controls) or 'no controls'}")
+        for i, name in enumerate(controls):
+            self.send_control_command(
+                name, "CLOSE", CommandPriority.SAFING, preempt=(i == 0)
+            )
+
+    def request_control(self, name: str, action: str) -> None:
+        """Operator request from the sidebar or the P&ID; confirms critical OPENs."""
+        spec = self._control_spec(name)
+        if spec.danger == "critical" and action == "OPEN":
+            confirm = QMessageBox.question(
+                self,
+                "Confirm",
+                f"{spec.label} is marked critical. Send OPEN {spec.name}?",
+                QMessageBox.StandardButton.Yes | QMessageBox.StandardButton.No,
+                QMessageBox.StandardButton.No,
+            )
+            if confirm != QMessageBox.StandardButton.Yes:
+                return
+        self.send_control_command(name, action)
 
-    def send_control_command(self, name: str, action: str) -> None:
+    def send_control_command(
+        self,
+        name: str,
+        action: str,
+        priority: CommandPriority = CommandPriority.NORMAL,
+        preempt: bool = False,
+    ) -> bool:
+        """Queue a CONTROL command; False if it was refused (e.g. interlocked)."""
         name = name.strip()
         if action not in {"OPEN", "CLOSE", "DEFAULT"}:
             QMessageBox.warning(self, "Command", f"Unknown action: {action}")
-            return
+            return False
 
         if (name, action) == ("ALL", "DEFAULT"):
             # Set each valve to its default state from deviceConfig
             if not isinstance(self.deviceConfig, dict):
-                return
+                return False
             for devName, devDict in self.deviceConfig.get("configs", {}).items():
                 for control, controlDict in devDict.get("controls", {}).items():
                     control_upper = control.upper()
@@ -1311,16 +4828,203 @@ class MainWindow(QMainWindow):
                         else:
                             continue
                         self.send_control_command(control_upper, action)
+            return True
+
+        # Safing commands are never interlocked or held back by role
+        if priority != CommandPriority.SAFING:
+            if not self._authorized(name, action) or self._interlocked(name, action):
+                return False
+
+        self.send_command(
+            {"command": "CONTROL", "args": [name, action]},
+            priority,
+            preempt,
+            prepare=lambda w: self._track_command(name, EXPECTED_STATE[action], w),
+        )
+        return True
+
+    def _interlocked(self, name: str, action: str, command: str = "") -> bool:
+        """True (and the block is reported) if an interlock refuses the command."""
+        reason = blocking_interlock(
+            self.interlocks, name.upper(), action, self.latest_value
+        )
+        if reason is None:
+            return False
+        command = command or action
+        text = f"INTERLOCK: {command} {name} blocked, {reason}"
+        self.append_log(text)
+        self.statusBar().showMessage(text, 10000)
+        self.controls_sidebar.set_ack_status(name, "INTERLOCKED", "red")
+        if self.logger.is_active:
+            self.logger.log_event("INTERLOCK", f"{command} {name}: {reason}")
+        return True
+
+    def send_setpoint(self, name: str, value: float) -> bool:
+        """Queue CONTROL <name> SET <value> for an analog control within its limits."""
+        spec = self.controls_sidebar.specs.get(name)
+        if spec is None or spec.kind != "analog":
+            QMessageBox.warning(self, "Command", f"{name} is not an analog control")
+            return False
+        if not spec.minimum <= value <= spec.maximum:
+            QMessageBox.warning(
+                self,
+                "Command",
+                f"{name}: {value:g} is outside {spec.minimum:g}..{spec.maximum:g}",
+            )
+            return False
+        if not self._authorized(name, "SET") or self._interlocked(name, "SET"):
+            return False
+        text = spec.format_value(value)
+        self.send_command(
+            {"command": "CONTROL", "args": [name, "SET", text]},
+            prepare=lambda w: self._track_command(name, text, w, spec.tolerance),
+        )
+        return True
+
+    def send_pulse(self, name: str, duration_ms: int) -> bool:
+        """Queue CONTROL <name> PULSE <ms>; the device opens, times and closes."""
+        spec = self.controls_sidebar.specs.get(name)
+        if spec is None or not spec.pulse_max_ms:
+            QMessageBox.warning(self, "Command", f"{name} has no pulse action")
+            return False
+        if not 0 < duration_ms <= spec.pulse_max_ms:
+            QMessageBox.warning(
+                self,
+                "Command",
+                f"{name}: {duration_ms} ms exceeds the {spec.pulse_max_ms} ms maximum",
+            )
+            return False
+        if name in self._pulsing or not self._authorized(name, "PULSE"):
+            return False
+        # A pulse opens the valve, so OPEN interlocks apply
+        if self._interlocked(name, "OPEN", "PULSE"):
+            return False
+
+        self.append_log(f"PULSE {name} {duration_ms} ms")
+        # The valve ends closed
+        self.control_states.setdefault(name, ControlState()).commanded = "CLOSED"
+        self.controls_sidebar.set_ack_status(name, "")
+
+        def prepare(worker: HttpRequestWorker) -> None:
+            self._pulsing[name] = worker
+            worker.signals.sent.connect(
+                lambda n=name, w=worker: self._start_pulse(n, w, duration_ms)
+            )
+            worker.signals.error.connect(
+                lambda _msg, n=name, w=worker: self._end_pulse(n, w)
+            )
+
+        self.send_command(
+            {"command": "CONTROL", "args": [name, "PULSE", str(duration_ms)]},
+            prepare=prepare,
+        )
+        return True
+
+    def _start_pulse(
+        self, name: str, worker: HttpRequestWorker, duration_ms: int
+    ) -> None:
+        """The pulse left the queue: show PULSE until the device's time is up."""
+        if self._pulsing.get(name) is not worker or worker.sent_at is None:
+            return
+        self.controls_sidebar.set_pulse_active(name, True)
+        self._refresh_control_indicator(name)
+        elapsed_ms = (time.monotonic() - worker.sent_at) * 1000
+        QTimer.singleShot(
+            max(0, round(duration_ms - elapsed_ms)),
+            lambda n=name, w=worker: self._end_pulse(n, w),
+        )
+
+    def _end_pulse(self, name: str, worker: HttpRequestWorker) -> None:
+        # A stale timer from an earlier pulse must not end a newer one
+        if self._pulsing.get(name) is not worker:
             return
+        del self._pulsing[name]
+        self.controls_sidebar.set_pulse_active(name, False)
+        self._refresh_control_indicator(name)
+
+    def _track_command(
+        self, name: str, expected: str, worker, tolerance: Optional[float] = None
+    ) -> None:
+        """Follow a sent command until the hardware reports the expected state."""
+        self._audit_ids[name] = worker.audit_id
+        self.command_tracker.track(name, expected, tolerance)
+        state = self.control_states.setdefault(name, ControlState())
+        state.commanded = expected
+        state.tolerance = tolerance
+        self.controls_sidebar.set_ack_status(name, "")
+        self._refresh_control_indicator(name)
+        worker.signals.error.connect(
+            lambda msg, n=name: self.command_tracker.fail(n, f"command failed: {msg}")
+        )
 
-        self.send_command({"command": "CONTROL", "args": [name, action]})
+    def on_command_confirmed(self, control: str, state: str, latency: float) -> None:
+        self.audit_record(
+            "confirmation",
+            command=self._audit_ids.pop(control, None),
+            control=control,
+            confirmed=True,
+            state=state,
+            latency_ms=round(latency * 1000, 1),
+        )
+        self.controls_sidebar.set_ack_status(control, "")
+        self._refresh_control_indicator(control)
+        self.append_log(f"CONFIRMED {control} {state} ({latency * 1000:.0f} ms)")
+
+    def on_command_unconfirmed(self, control: str, expected: str, reason: str) -> None:
+        self.audit_record(
+            "confirmation",
+            command=self._audit_ids.pop(control, None),
+            control=control,
+            confirmed=False,
+            expected=expected,
+            reason=reason,
+        )
+        self.controls_sidebar.set_ack_status(control, "UNCONFIRMED", "red")
+        self._refresh_control_indicator(control)
+        self.append_log(f"UNCONFIRMED {control}: expected {expected}, {reason}")
+
+    def report_control_state(self, control: str, state: str) -> None:
+        """Record a state reported by CONTROL or STATUS telemetry."""
+        entry = self.control_states.setdefault(control, ControlState())
+        entry.reported = state
+        entry.reported_at = time.monotonic()
+        self.command_tracker.report(control, state)
+        self._refresh_control_indicator(control)
+
+    def _refresh_control_indicator(self, control: str) -> None:
+        state = self.control_states.get(control, ControlState())
+        text, color = control_indicator(
+            state, self.command_tracker.is_pending(control)
+        )
+        pulse = self._pulsing.get(control)
+        if pulse is not None and pulse.sent_at is not None:
+            text, color = "PULSE", "#b58900"
+        stale_s = self.config.control_stale_s
+        stale = (
+            bool(state.reported)
+            and stale_s > 0
+            and time.monotonic() - state.reported_at > stale_s
+        )
+        self.controls_sidebar.set_indicator(control, text, color, stale)
+        self.pid_panel.set_valve_state(control, text, color, stale)
+
+    @Slot()
+    def _refresh_control_indicators(self) -> None:
+        controls = set(self.controls_sidebar.controlIndicators)
+        controls.update(self.config.pid_valves)
+        for control in controls:
+            self._refresh_control_indicator(control.upper())
 
     def on_gets(self) -> None:
+        if self.role == "observer" or not self._has_authority():
+            return
         self.btn_gets.setEnabled(False)
         w = self.send_command({"command": "GETS", "args": []})
         w.signals.finished.connect(lambda: self.btn_gets.setEnabled(True))
 
     def on_stream(self) -> None:
+        if self.role == "observer" or not self._has_authority():
+            return
         text = self.stream_rate_edit.text().strip()
         try:
             hz = int(text)
@@ -1332,6 +5036,7 @@ class MainWindow(QMainWindow):
         self.send_command({"command": "STREAM", "args": [str(hz)]})
         self.stream_rate_edit.setEnabled(False)
         self.btn_stream.setEnabled(False)
+        self.set_panel_output("STREAMING", True)
 
         if not self.logger.is_active:
             self._test_start_dt = datetime.now()
@@ -1341,9 +5046,12 @@ class MainWindow(QMainWindow):
             self.append_log(f"Logging to {self.logger.path}")
 
     def on_stop(self) -> None:
+        if self.role == "observer" or not self._has_authority():
+            return
         self.send_command({"command": "STOP", "args": []})
         self.btn_stream.setEnabled(True)
         self.stream_rate_edit.setEnabled(True)
+        self.set_panel_output("STREAMING", False)
 
         # Stop logging
         if self.logger.is_active:
@@ -1353,68 +5061,124 @@ class MainWindow(QMainWindow):
             if p:
                 self.append_log(f"Log saved: {p}")
 
-    def on_redis_message(self, m: str) -> None:
-        m = m.split("]", 1)[-1]  # strip any leading timestamp
-        m = m.strip()
-
-        # Match data with optional timestamp: "DEVICE [<t>] NAME: VAL" or "DEVICE <t> NAME:VAL"
-        DATA_LOG_RE = re.compile(
-            r"^(?P<device>\S+)\s+(?:(?P<t>[+-]?\d+(?:\.\d+)?)\s+)?(?P<name>\S+):\s*(?P<val>[+-]?\d+(?:\.\d+)?)$"
-        )
-        CONTROL_LOG_RE = re.compile(
-            r"^(?P<device>\S+)\s+CONTROL\s+(?P<control>\S+)\s+(?P<action>\S+)$"
-        )
-        STATUS_LOG_RE = re.compile(r"^(?P<device>\S+):\s+STATUS\s+(?P<status>.+)$")
-
-        # Parse out data values
-        dataMatch = DATA_LOG_RE.match(m)
-        if dataMatch:
-            self.handleDataString(dataMatch)
-            return
-
-        controlMatch = CONTROL_LOG_RE.match(m)
-        if controlMatch:
-            self.append_log(f"[DEBUG] Control match: {m}")
-            self.handleControlString(controlMatch)
-            return
-
-        statusMatch = STATUS_LOG_RE.match(m)
-        if statusMatch:
-            self.handleStatusString(statusMatch)
-            return
-
-    def handleDataString(self, data: re.Match) -> None:
-        # Process the incoming data string
-        try:
-            device = data.group("device")
-            t_str = data.group("t")
-            # Use current time if timestamp not provided
-            t = float(t_str) if t_str else time.time()
-            name = data.group("name")
-            val = float(data.group("val"))
-        except Exception:
-            return
+    def latest_value(self, name: str) -> Optional[float]:
+        """Latest sample for 'DEVICE:NAME' or a bare 'NAME' (any device);
+        None if nothing arrived within sensor_stale_s."""
+        entry = self.latest_values.get(name)
+        if entry is None:
+            return None
+        value, received = entry
+        stale_s = self.config.sensor_stale_s
+        if stale_s > 0 and time.monotonic() - received > stale_s:
+            return None
+        return value
+
+    def _set_latest(self, series: str, name: str, value: float) -> None:
+        entry = (value, time.monotonic())
+        self.latest_values[series] = entry
+        self.latest_values[name] = entry
+
+    @Slot(object)
+    def on_redis_batch(self, batch: TelemetryBatch) -> None:
+        for event in batch.events:
+            if isinstance(event, Sample):
+                self.handleDataString(event)
+            elif isinstance(event, ControlEvent):
+                self.handleControlString(event)
+            elif isinstance(event, StatusEvent):
+                self.handleStatusString(event)
+            else:
+                self.append_log(f"[redis] {event}")
 
-        if not (isfinite(t) and isfinite(val)):
-            return
+    def handleDataString(self, sample: Sample) -> None:
+        device, t, name, val = sample
 
         # Keep device streams separate so they don't merge
         series = f"{device}:{name}"
         self._pending_points.append((series, t, val))
+        self._set_latest(series, name, val)
+        self._last_sample_t = t
+        self._device_t[device] = t
+        for rule in self.redlines.evaluate(series, name, t, val):
+            self.on_redline(rule, series, val)
 
         if self.logger.is_active:
             self.logger.log(device, t, name, val)
 
-    def handleControlString(self, data: re.Match) -> None:
-        # device not used here, but parsed for completeness
-        _device = data.group("device")
-        control = data.group("control").upper()
-        action = data.group("action").upper()
+    def _go_checks(self) -> list[tuple[str, bool]]:
+        """Go/no-go poll for the countdown: automatic checks + operator polls."""
+        checks = [
+            ("Armed and authorized", self._commanding_allowed()),
+            ("Telemetry connected", self._redis_state == "connected"),
+            ("Data logging", self.logger.is_active),
+            ("No active alarms", not self.alarm_banner.has_alarms()),
+            ("Sequencer idle", self.sequencer.state not in ("running", "holding")),
+            ("Firing sequence configured", bool(self.config.firing_sequence)),
+        ]
+        # Every command of the firing sequence must be allowed right now, or
+        # the sequencer would be refused mid-sequence after T-0
+        refused, interlocked = [], []
+        for step in self.config.firing_sequence:
+            if step.get("type") != "command":
+                continue
+            name, action = str(step["control"]).upper(), str(step["action"]).upper()
+            if self._refusal(name) is not None:
+                refused.append(f"{action} {name}")
+            elif blocking_interlock(self.interlocks, name, action, self.latest_value):
+                interlocked.append(f"{action} {name}")
+        for label, blocked in (
+            ("Role may command the firing sequence", refused),
+            ("Firing sequence not interlocked", interlocked),
+        ):
+            if blocked:
+                label += f" ({', '.join(blocked)})"
+            checks.append((label, not blocked))
+        for cond in self._go_conditions:
+            result = cond.evaluate(self.latest_value)
+            # A stale reading is NO-GO, never the last value seen
+            label = f"{cond} (no recent data)" if result is None else str(cond)
+            checks.append((label, bool(result)))
+        return checks + self.countdown_panel.manual_checks()
+
+    @Slot(str)
+    def on_countdown_message(self, text: str) -> None:
+        self.append_log(f"[COUNT] {text}")
+        if self.logger.is_active:
+            self.logger.log_event("COUNTDOWN", text)
+
+    @Slot()
+    def on_ignition(self) -> None:
+        # T-0 epoch: wall clock plus the device clock of the latest sample, so
+        # logged data can be re-based to ignition
+        if self.logger.is_active:
+            self.logger.mark_t0(self._last_sample_t, time.time())
+        self.sequencer.load("Firing sequence", list(self.config.firing_sequence))
+        self.act_show_sequencer.setChecked(True)
+        self.sequencer.start()
+
+    def on_redline(self, rule: Redline, series: str, value: float) -> None:
+        text = f"REDLINE {rule.name}: {series} = {value:g} ({rule.condition})"
+        if self.logger.is_active:
+            self.logger.log_event("REDLINE", text)
+        self.raise_alarm(text)
+        if rule.action == "abort":
+            self.run_safing(f"redline {rule.name}")
+
+    def handleControlString(self, event: ControlEvent) -> None:
+        device, control, action, value = event
 
         self.append_log(
             f"[DEBUG] handleControlString: control={control}, action={action}"
         )
 
+        if value is not None:
+            self.handleSetpointEcho(device, control, value)
+            return
+
+        state = REPORTED_STATE.get(action)
+        if state is not None:
+            self.report_control_state(control, state)
+
         # Handle both "OPEN"/"OPENED" and "CLOSE"/"CLOSED"
         if action in ("OPEN", "OPENED"):
             self.append_log(f"[DEBUG] Setting {control} to OPEN state")
@@ -1429,15 +5193,32 @@ class MainWindow(QMainWindow):
             if f"{control}_open" in self.controlButtons:
                 self.controlButtons[f"{control}_open"].setEnabled(True)
 
-    def handleStatusString(self, data: re.Match) -> None:
-        # device not used here, but parsed for completeness
-        _device = data.group("device")
-        status = data.group("status")
-
-        ctlStatusDict = json.loads(status).get("controls", {})
+    def handleSetpointEcho(self, device: str, control: str, value: float) -> None:
+        """Reported position of an analog control: confirm it and plot it."""
+        spec = self.controls_sidebar.specs.get(control)
+        text = spec.format_value(value) if spec is not None else f"{value:g}"
+        self.report_control_state(control, text)
+        # Echoes carry no timestamp; put them on the clock of the same device's
+        # latest sample, never before the previous echo of this series
+        series = f"{device}:{control}"
+        t = self._device_t.get(device, time.time())
+        t = max(t, self._echo_t.get(series, t))
+        self._echo_t[series] = t
+        self._pending_points.append((series, t, value))
+        self._set_latest(series, control, value)
+
+    def handleStatusString(self, event: StatusEvent) -> None:
+        device, status = event
+
+        ctlStatusDict = status.get("controls", {})
 
         for control, state in ctlStatusDict.items():
             control = control.upper()
+            if isinstance(state, (int, float)) and not isinstance(state, bool):
+                self.handleSetpointEcho(device, control, float(state))
+                continue
+            if state in ("OPEN", "CLOSED"):
+                self.report_control_state(control, state)
             if state == "OPEN":
                 if f"{control}_open" in self.controlButtons:
                     self.controlButtons[f"{control}_open"].setEnabled(False)
@@ -1449,13 +5230,34 @@ class MainWindow(QMainWindow):
                 if f"{control}_open" in self.controlButtons:
                     self.controlButtons[f"{control}_open"].setEnabled(True)
 
+    def _redact(self, line: str) -> str:
+        for secret in (self.config.api_password, self.config.redis_password):
+            if secret:
+                line = line.replace(secret, "****")
+        return line
+
     def append_log(self, line: str) -> None:
+        line = self._redact(line)
         self.log.append(line)
         self.statusBar().showMessage(line, 3000)
 
     def closeEvent(self, event) -> None:
+        # Each step runs even if an earlier one fails; above all, the data log
+        # must be flushed
+        steps = [
+            self.stop_redis,
+            self.stop_lease,
+            self.stop_hardware_panel,
+            self.dispatcher.stop,
+            lambda: self.dispatcher.wait(3000),
+            self.close_audit,
+        ]
+        for step in steps:
+            try:
+                step()
+            except Exception as e:
+                print(f"Shutdown step failed: {e!r}", file=sys.stderr)
         try:
-            self.stop_redis()
             if self.logger.is_active:
                 self._log_timer.stop()
                 self.logger.stop()
@@ -1478,9 +5280,149 @@ class MainWindow(QMainWindow):
         return base, auth
 
 
+def parse_args(argv: list[str]) -> tuple[argparse.Namespace, list[str]]:
+    """Parse our own flags; anything unrecognised is passed through to Qt."""
+    parser = argparse.ArgumentParser(description="Prop control GUI", allow_abbrev=False)
+    parser.add_argument(
+        "--settings",
+        type=Path,
+        default=default_settings_path(),
+        help="settings JSON file (default: %(default)s)",
+    )
+    parser.add_argument("--api-base", help="backend base URL")
+    parser.add_argument("--commands-path", help="backend command endpoint path")
+    parser.add_argument("--redis-host", help="Redis host")
+    parser.add_argument("--redis-port", type=int, help="Redis port")
+    parser.add_argument("--redis-channel", help="Redis pub/sub channel")
+    parser.add_argument(
+        "--redis-mode",
+        choices=["pubsub", "stream"],
+        help="read telemetry from the pub/sub channel or from a Redis stream",
+    )
+    parser.add_argument("--redis-stream", help="Redis stream key (stream mode)")
+    parser.add_argument("--profile", help="station profile to start with")
+    parser.add_argument(
+        "--key-switch-port", help="key switch serial port (empty to auto-detect)"
+    )
+    parser.add_argument(
+        "--key-switch-serial", help="USB serial number of the key switch adapter"
+    )
+    parser.add_argument("--log-dir", help="directory for CSV test logs")
+    parser.add_argument(
+        "--credential-backend",
+        choices=["auto", "keyring", "file"],
+        help="where credentials are stored",
+    )
+    parser.add_argument(
+        "--verify-audit",
+        type=Path,
+        metavar="FILE",
+        help="check a command audit trail against this station's audit key and exit",
+    )
+    parser.add_argument(
+        "--save-settings",
+        action="store_true",
+        help="write the effective settings (file + overrides) back to the file",
+    )
+    return parser.parse_known_args(argv[1:])
+
+
+def load_config(args: argparse.Namespace) -> Config:
+    """Build the effective config: defaults <- settings file <- CLI flags.
+    A settings file is created on first run so it can be edited."""
+    path: Path = args.settings
+    try:
+        if path.exists():
+            config = Config.load(path)
+            # Older settings files carried credentials in plain text
+            config.legacy_credentials = {
+                k: v for k, v in config.credentials().items() if v
+            }
+        else:
+            config = Config()
+            config.save(path)
+            print(f"Created settings file {path}", file=sys.stderr)
+    except (OSError, ValueError) as e:
+        raise SystemExit(f"Invalid settings file {path}: {e}")
+
+    overrides = {
+        f.name: getattr(args, f.name)
+        for f in fields(Config)
+        if getattr(args, f.name, None) is not None
+    }
+    profile = overrides.pop("profile", config.profile)
+    try:
+        if profile not in config.profiles:
+            raise ValueError(f"unknown profile: {profile}")
+        if args.save_settings:
+            # Save before applying the profile so CLI values become the base
+            config.update(overrides)
+            config.profile = profile
+            config.mark_explicit([*overrides, "profile"])
+            config.save(path)
+        config.apply_profile(profile)
+        # Flags beat the profile for this run
+        config.update(overrides)
+    except ValueError as e:
+        raise SystemExit(f"Invalid settings: {e}")
+    return config
+
+
+def migrate_legacy_credentials(
+    path: Path, config: Config, store: CredentialStore
+) -> None:
+    """Move plain-text credentials from an old settings file into the store
+    (for the active profile, unless it already has some) and strip the file."""
+    legacy = getattr(config, "legacy_credentials", None)
+    if not legacy or store.needs_unlock:
+        return
+    try:
+        if not any(store.load(config.profile).values()):
+            store.save(config.profile, legacy)
+        data = json.loads(path.read_text(encoding="utf-8"))
+        for key in CREDENTIAL_KEYS:
+            data.pop(key, None)
+        path.write_text(json.dumps(data, indent=2) + "\n", encoding="utf-8")
+        print(f"Moved plain-text credentials out of {path}", file=sys.stderr)
+    except Exception as e:
+        print(f"Could not migrate credentials: {e}", file=sys.stderr)
+
+
+def verify_audit(path: Path, config: Config) -> int:
+    """--verify-audit: check a trail with the key from the credential store."""
+    try:
+        store = CredentialStore(
+            user_config_dir() / "credentials.enc", config.credential_backend
+        )
+        if store.needs_unlock:
+            passphrase = os.environ.get(CredentialStore.PASSPHRASE_ENV)
+            if not passphrase:
+                passphrase = getpass.getpass("Passphrase for stored credentials: ")
+            store.unlock(passphrase)
+        ok, message = verify_audit_file(path, store.audit_key(create=False))
+    except (OSError, RuntimeError, ValueError) as e:
+        ok, message = False, f"cannot verify: {e}"
+    print(f"{path}: {message}")
+    return 0 if ok else 1
+
+
 def main() -> int:
-    app = QApplication(sys.argv)
-    win = MainWindow(CONFIG)
+    args, qt_args = parse_args(sys.argv)
+    config = load_config(args)
+    if args.verify_audit:
+        return verify_audit(args.verify_audit, config)
+    app = QApplication([sys.argv[0], *qt_args])
+
+    try:
+        store = CredentialStore(
+            user_config_dir() / "credentials.enc", config.credential_backend
+        )
+    except (RuntimeError, ValueError) as e:
+        raise SystemExit(f"Credential store: {e}")
+    unlock_credential_store(store)
+    migrate_legacy_credentials(args.settings, config, store)
+
+    win = MainWindow(config, store)
     win.showMaximized()
     return app.exec()
 
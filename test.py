@@ -0,0 +1,367 @@
+import json
+import os
+import tempfile
+import unittest
+from pathlib import Path
+
+from PySide6.QtCore import QEvent, QPoint, Qt
+from PySide6.QtWidgets import QGraphicsSceneMouseEvent
+
+import main
+
+
+class ConfigTest(unittest.TestCase):
+    def test_update_coerces_to_field_types(self):
+        cfg = main.Config()
+        cfg.update(
+            {
+                "redis_port": "6380",
+                "command_ack_timeout_s": "1.5",
+                "key_switch_required": "off",
+                "key_switch_usb_ids": [1234],
+            }
+        )
+        self.assertEqual(cfg.redis_port, 6380)
+        self.assertEqual(cfg.command_ack_timeout_s, 1.5)
+        self.assertIs(cfg.key_switch_required, False)
+        self.assertEqual(cfg.key_switch_usb_ids, ["1234"])
+
+    def test_update_rejects_unknown_and_invalid(self):
+        cfg = main.Config()
+        with self.assertRaises(ValueError):
+            cfg.update({"no_such_setting": 1})
+        with self.assertRaises(ValueError):
+            cfg.update({"redis_port": "not a number"})
+        with self.assertRaises(ValueError):
+            cfg.update({"profiles": {"bench": {"api_password": "x"}}})
+        with self.assertRaises(ValueError):
+            cfg.update({"redis_port": None})
+        for cond in [
+            {"sensor": "PTN2", "op": ">"},
+            {"sensor": "PTN2", "op": ">", "value": None},
+        ]:
+            with self.assertRaisesRegex(ValueError, "go condition 1", msg=cond):
+                cfg.update({"go_conditions": [cond]})
+
+    def test_bad_profile_value_is_rejected_before_switching(self):
+        cfg = main.Config()
+        with self.assertRaises(ValueError):
+            cfg.update({"profiles": {"pad": {"redis_port": "x"}}})
+        # A profile edited after loading still cannot half-apply
+        cfg.profiles["pad"] = {"api_base": "http://pad", "redis_port": "x"}
+        with self.assertRaises(ValueError):
+            cfg.apply_profile("pad")
+        self.assertEqual(cfg.api_base, "http://localhost:8000")
+        self.assertEqual(cfg.profile, "bench")
+
+    def test_save_writes_only_explicit_and_changed_values(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            path = Path(tmp) / "settings.json"
+            main.Config().save(path)
+            self.assertEqual(json.loads(path.read_text()), {})
+
+            path.write_text(json.dumps({"redis_port": 6379}))
+            cfg = main.Config.load(path)
+            cfg.update({"redis_host": "pad", "api_password": "secret"})
+            cfg.save(path)
+            self.assertEqual(
+                json.loads(path.read_text()),
+                {"redis_port": 6379, "redis_host": "pad"},
+            )
+
+
+class AbortSequenceStepsTest(unittest.TestCase):
+    def test_accepts_commands_and_delays(self):
+        steps = [
+            {"control": "AVMAINOX", "action": "close"},
+            {"delay_s": 0.5},
+            {"control": "AVVENT", "action": "OPEN"},
+        ]
+        self.assertIs(main.check_sequence_steps(steps), steps)
+
+    def test_rejects_invalid_steps(self):
+        for step in [
+            {"delay_s": None},
+            {"delay_s": "soon"},
+            {"delay_s": -1},
+            {"delay_s": 1, "control": "AVVENT"},
+            {"control": "AVVENT"},
+            {"control": "AVVENT", "action": "VENT"},
+            "AVVENT",
+        ]:
+            with self.assertRaises(ValueError, msg=step):
+                main.check_sequence_steps([step])
+
+
+class TelemetryJsonTest(unittest.TestCase):
+    def test_values_controls_and_status(self):
+        batch = main.TelemetryBatch()
+        frame = {
+            "device": "E",
+            "timestamp": 1.5,
+            "values": {"PT": "2.5", "bad": "x", "inf": float("inf")},
+            "controls": {"av1": "opened", "THR": 42.5},
+            "status": {"controls": {"AV1": "OPEN"}},
+        }
+        self.assertTrue(main.parse_telemetry_json(frame, batch))
+        self.assertEqual(batch.samples, [main.Sample("E", 1.5, "PT", 2.5)])
+        self.assertEqual(
+            batch.controls,
+            [
+                main.ControlEvent("E", "AV1", "OPENED"),
+                main.ControlEvent("E", "THR", "SET", 42.5),
+            ],
+        )
+        self.assertEqual(
+            batch.statuses, [main.StatusEvent("E", {"controls": {"AV1": "OPEN"}})]
+        )
+        self.assertEqual(len(batch.lines), 1)
+
+    def test_array_keeps_frame_order(self):
+        batch = main.TelemetryBatch()
+        frames = [
+            {"device": "E", "status": {"controls": {"AV1": "CLOSED"}}},
+            {"device": "E", "controls": {"AV1": "OPENED"}},
+        ]
+        self.assertTrue(main.parse_telemetry_json(frames, batch))
+        kinds = [type(e) for e in batch.events if not isinstance(e, str)]
+        self.assertEqual(kinds, [main.StatusEvent, main.ControlEvent])
+
+    def test_rejects_frames_outside_schema(self):
+        for frame in [[], {"values": {"A": 1}}, {"device": "E"}, {"device": 1}, 3]:
+            batch = main.TelemetryBatch()
+            self.assertFalse(main.parse_telemetry_json(frame, batch), frame)
+
+
+class PanelFrameTest(unittest.TestCase):
+    def test_round_trip(self):
+        raw = main.encode_panel_frame("KEY", 1)
+        self.assertEqual(raw, b"$KEY,1*4A\n")
+        self.assertEqual(main.decode_panel_frame(raw), main.PanelFrame("KEY", ["1"]))
+        self.assertEqual(
+            main.decode_panel_frame(b"$ABORT,0*56\r\n"),
+            main.PanelFrame("ABORT", ["0"]),
+        )
+
+    def test_rejects_unchecked_and_corrupt_frames(self):
+        for raw in [
+            b"1\n",
+            b"0\n",
+            b"$KEY,1\n",
+            b"$KEY,1*00\n",
+            b"$KEY,0*4A\n",
+            b"$*00\n",
+            b"\xff\n",
+        ]:
+            with self.assertRaises(main.FrameError, msg=raw):
+                main.decode_panel_frame(raw)
+
+
+class RedlineTest(unittest.TestCase):
+    RULE = {"sensor": "PTRun", "op": ">", "value": 800, "duration_ms": 50}
+
+    def test_trips_once_after_duration_and_rearms(self):
+        monitor = main.RedlineMonitor([self.RULE])
+        trips = [
+            monitor.evaluate("E:PTRun", "PTRun", t, v)
+            for t, v in [(0.0, 900), (0.04, 900), (0.06, 900), (0.07, 900)]
+        ]
+        self.assertEqual([len(fired) for fired in trips], [0, 0, 1, 0])
+        # Recovery re-arms the rule and restarts the duration
+        self.assertEqual(monitor.evaluate("E:PTRun", "PTRun", 0.1, 100), [])
+        self.assertEqual(monitor.evaluate("E:PTRun", "PTRun", 0.12, 900), [])
+        self.assertEqual(len(monitor.evaluate("E:PTRun", "PTRun", 0.17, 900)), 1)
+
+    def test_device_qualified_sensor_only_matches_that_series(self):
+        monitor = main.RedlineMonitor([dict(self.RULE, sensor="A:PTRun")])
+        self.assertEqual(monitor.evaluate("B:PTRun", "PTRun", 0.0, 900), [])
+        self.assertEqual(monitor.evaluate("B:PTRun", "PTRun", 1.0, 900), [])
+        self.assertEqual(monitor.evaluate("A:PTRun", "PTRun", 0.0, 900), [])
+        self.assertEqual(len(monitor.evaluate("A:PTRun", "PTRun", 1.0, 900)), 1)
+
+    def test_invalid_rules_are_rejected(self):
+        for rule in [
+            dict(self.RULE, op="~"),
+            dict(self.RULE, action="explode"),
+            dict(self.RULE, duration_ms=-1),
+            {"op": ">", "value": 1},
+        ]:
+            with self.assertRaises(ValueError, msg=rule):
+                main.check_redlines([rule])
+
+
+class InterlockTest(unittest.TestCase):
+    def setUp(self):
+        rule = {
+            "control": "avox",
+            "action": "OPEN",
+            "sensor": "PTPre",
+            "op": ">",
+            "value": 300,
+        }
+        self.interlocks = [main.Interlock.from_dict(rule)]
+
+    def blocked(self, control, action, values):
+        return main.blocking_interlock(self.interlocks, control, action, values.get)
+
+    def test_blocks_while_condition_holds(self):
+        self.assertIn("PTPre > 300", self.blocked("AVOX", "OPEN", {"PTPre": 400}))
+        self.assertIsNone(self.blocked("AVOX", "OPEN", {"PTPre": 100}))
+
+    def test_missing_or_stale_reading_blocks(self):
+        self.assertIn("no recent data", self.blocked("AVOX", "OPEN", {}))
+
+    def test_other_commands_are_not_affected(self):
+        self.assertIsNone(self.blocked("AVOX", "CLOSE", {"PTPre": 400}))
+        self.assertIsNone(self.blocked("AVFUEL", "OPEN", {"PTPre": 400}))
+
+
+class ProcedureStepsTest(unittest.TestCase):
+    def test_accepts_every_step_type(self):
+        steps = [
+            {"type": "command", "control": "AVFILL", "action": "open"},
+            {"type": "wait", "seconds": 2},
+            {
+                "type": "wait_until",
+                "sensor": "PTN2",
+                "op": ">",
+                "value": 700,
+                "timeout_s": 60,
+                "on_timeout": "abort",
+            },
+            {"type": "hold", "message": "Confirm pad clear"},
+            {
+                "type": "log",
+                "message": "Fill complete",
+                "when": {"sensor": "PTN2", "op": ">=", "value": 1},
+            },
+        ]
+        self.assertIs(main.check_procedure_steps(steps), steps)
+
+    def test_rejects_invalid_steps(self):
+        for step in [
+            {"type": "fire"},
+            {"type": "command", "control": "AVFILL", "action": "VENT"},
+            {"type": "command", "action": "OPEN"},
+            {"type": "wait", "seconds": -1},
+            {"type": "wait_until", "sensor": "PT", "op": "~", "value": 1},
+            {"type": "wait_until", "sensor": "PT", "op": ">", "value": "high"},
+            {
+                "type": "wait_until",
+                "sensor": "PT",
+                "op": ">",
+                "value": 1,
+                "on_timeout": "retry",
+            },
+            {"type": "log", "when": {"op": ">", "value": 1}},
+        ]:
+            with self.assertRaises(ValueError, msg=step):
+                main.check_procedure_steps([step])
+        with self.assertRaises(ValueError):
+            main.check_procedure_steps({"type": "log"})
+
+
+class AuditTrailTest(unittest.TestCase):
+    KEY = bytes(range(32))
+
+    def setUp(self):
+        tmp = tempfile.TemporaryDirectory()
+        self.addCleanup(tmp.cleanup)
+        self.dir = Path(tmp.name)
+        self.path = self.dir / "audit.jsonl"
+        audit = main.AuditLog(self.path, self.KEY)
+        audit.record("session_start")
+        seq = audit.record("command", payload={"args": ["AVOX", "OPEN"]})
+        audit.record("result", command=seq, ok=True)
+        audit.close()
+
+    def write(self, lines):
+        path = self.dir / "edited.jsonl"
+        path.write_text("".join(json.dumps(e) + "\n" for e in lines))
+        return path
+
+    def records(self):
+        return [json.loads(line) for line in self.path.read_text().splitlines()]
+
+    def test_intact_trail_verifies(self):
+        self.assertEqual(
+            main.verify_audit_file(self.path, self.KEY), (True, "chain intact")
+        )
+
+    def test_wrong_or_missing_key_fails(self):
+        self.assertFalse(main.verify_audit_file(self.path, bytes(32))[0])
+        self.assertFalse(main.verify_audit_file(self.path, None)[0])
+
+    def test_edited_record_fails(self):
+        records = self.records()
+        records[1]["payload"]["args"][1] = "CLOSE"
+        ok, message = main.verify_audit_file(self.write(records), self.KEY)
+        self.assertFalse(ok)
+        self.assertIn("line 2", message)
+
+    def test_removed_record_fails(self):
+        records = self.records()
+        del records[1]
+        self.assertFalse(main.verify_audit_file(self.write(records), self.KEY)[0])
+
+    def test_full_rewrite_without_key_fails(self):
+        # Recompute every hash as plain SHA-256, as someone without the key could
+        records, prev = [], main.AUDIT_GENESIS
+        for entry in self.records():
+            entry.update(mac="sha256", prev=prev)
+            entry["hash"] = prev = main.audit_hash(entry, None)
+            records.append(entry)
+        ok, message = main.verify_audit_file(self.write(records), self.KEY)
+        self.assertFalse(ok)
+        self.assertIn("not keyed", message)
+
+    def test_truncated_trail_is_reported(self):
+        records = self.records()[:-1]
+        ok, message = main.verify_audit_file(self.write(records), self.KEY)
+        self.assertFalse(ok)
+        self.assertIn("no session_end", message)
+
+
+class PidValveItemTest(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        os.environ.setdefault("QT_QPA_PLATFORM", "offscreen")
+        cls.app = main.QApplication.instance() or main.QApplication([])
+
+    def press(self, button):
+        clicks = []
+        item = main.PidValveItem(
+            "IGN", main.QRectF(0, 0, 10, 10), lambda *args: clicks.append(args)
+        )
+        event = QGraphicsSceneMouseEvent(QEvent.Type.GraphicsSceneMousePress)
+        event.setButton(button)
+        event.setScreenPos(QPoint(12, 34))
+        item.mousePressEvent(event)
+        return clicks
+
+    def test_left_click_reports_screen_position(self):
+        clicks = self.press(Qt.MouseButton.LeftButton)
+        self.assertEqual(clicks, [("IGN", QPoint(12, 34))])
+
+    def test_other_buttons_are_ignored(self):
+        self.assertEqual(self.press(Qt.MouseButton.RightButton), [])
+
+
+class RoleFromAuthTest(unittest.TestCase):
+    def test_single_role(self):
+        self.assertEqual(main.role_from_auth({"role": "operator"}), "operator")
+        self.assertEqual(
+            main.role_from_auth({"role": "Test Director"}), "test_director"
+        )
+
+    def test_highest_of_several_roles_wins(self):
+        payload = {"roles": ["observer", "test-director", "operator"]}
+        self.assertEqual(main.role_from_auth(payload), "test_director")
+
+    def test_missing_or_unknown_role_is_observer(self):
+        for payload in [None, "operator", {}, {"role": "admin"}, {"roles": []}]:
+            self.assertEqual(main.role_from_auth(payload), "observer", payload)
+
+
+if __name__ == "__main__":
+    unittest.main()